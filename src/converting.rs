@@ -1,41 +1,197 @@
 use crate::{
-    producing::{produce_decorate_wad, produce_s_skin_and_fist_wads, produce_s_skin_wad},
+    previewing::produce_contact_sheet,
+    producing::{
+        grab_renders, produce_decorate_wad, produce_s_skin_and_fist_wads, produce_s_skin_wad,
+    },
     rendering::{render_fist, render_mugshot, render_skin_with_crouch},
+    validating::decode_skin,
 };
 use image::DynamicImage;
 use putpng::crc::Crc32;
-use std::path::{Path, PathBuf};
+use std::{
+    cell::OnceCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        Arc, Mutex, Once,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+};
 use tempfile::tempdir;
 use three_d::*;
 
-pub trait SpritePrefix {
-    fn to_skin_sprite(&self) -> String;
-    fn to_crouched_skin_sprite(&self) -> String;
-    fn to_mugshot_sprite(&self) -> String;
-    fn to_fist_sprite(&self) -> String;
-    fn quoted(&self) -> String;
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct SpriteNames {
+    pub skin: String,
+    pub crouch: String,
+    pub fist: String,
+    pub face: String,
 }
 
-impl SpritePrefix for str {
-    fn to_skin_sprite(&self) -> String {
-        self.to_string() + "]"
+impl SpriteNames {
+    pub fn from_prefix(sprite_prefix: &str) -> Self {
+        let sprite_prefix = sprite_prefix.to_uppercase();
+        Self {
+            skin: sprite_prefix.clone() + "]",
+            crouch: sprite_prefix.clone() + "[",
+            fist: sprite_prefix.clone() + "\\",
+            face: sprite_prefix,
+        }
+    }
+
+    pub fn parse(sprites: &str) -> anyhow::Result<Self> {
+        let names = sprites
+            .split_whitespace()
+            .map(str::to_uppercase)
+            .collect::<Vec<_>>();
+        let [skin, crouch, fist, face] = <[String; 4]>::try_from(names)
+            .map_err(|_| anyhow::anyhow!("Must be 4 names: skin, crouch, fist and face"))?;
+        for sprite in [&skin, &crouch, &fist] {
+            if sprite.len() != 4 || !sprite.chars().all(|c| c.is_ascii_alphanumeric()) {
+                anyhow::bail!("Sprite {sprite} must be 4 letters or digits");
+            }
+        }
+        if face.len() != 3 || !face.chars().all(|c| c.is_ascii_alphanumeric()) {
+            anyhow::bail!("Face {face} must be 3 letters or digits");
+        }
+        let sprites = Self {
+            skin,
+            crouch,
+            fist,
+            face,
+        };
+        let roles = [&sprites.skin, &sprites.crouch, &sprites.fist];
+        if roles[0] == roles[1] || roles[0] == roles[2] || roles[1] == roles[2] {
+            anyhow::bail!("Skin, crouch and fist sprites must differ");
+        }
+        if let Some(lump) = sprites
+            .to_mugshot_lumps()
+            .into_iter()
+            .find(|lump| roles.iter().any(|sprite| lump.starts_with(sprite.as_str())))
+        {
+            anyhow::bail!("Face lump {lump} clashes with a sprite");
+        }
+        Ok(sprites)
     }
 
-    fn to_crouched_skin_sprite(&self) -> String {
-        self.to_string() + "["
+    pub fn to_mugshot_lumps(&self) -> Vec<String> {
+        let face = &self.face;
+        let mut lumps = vec![format!("{face}DEAD0"), format!("{face}GOD0")];
+        for level in 0..5 {
+            for direction in 0..3 {
+                lumps.push(format!("{face}ST{level}{direction}"));
+            }
+            lumps.push(format!("{face}TL{level}0"));
+            lumps.push(format!("{face}TR{level}0"));
+            lumps.push(format!("{face}OUCH{level}"));
+            lumps.push(format!("{face}EVL{level}"));
+            lumps.push(format!("{face}KILL{level}"));
+        }
+        lumps
     }
 
-    fn to_mugshot_sprite(&self) -> String {
-        self.to_string()
+    pub fn to_lump_names(&self) -> Vec<String> {
+        let mut names = vec![self.skin.clone(), self.crouch.clone(), self.fist.clone()];
+        names.extend(self.to_mugshot_lumps());
+        names
     }
+}
+
+/// The sprites of DOOM2.WAD, which cover those of the original Doom. Other IWADs such as Heretic
+/// or Hexen are not checked.
+const IWAD_SPRITES: [&str; 138] = [
+    "TROO", "SHTG", "PUNG", "PISG", "PISF", "SHTF", "SHT2", "CHGG", "CHGF", "MISG", "MISF", "SAWG",
+    "PLSG", "PLSF", "BFGG", "BFGF", "BLUD", "PUFF", "BAL1", "BAL2", "PLSS", "PLSE", "MISL", "BFS1",
+    "BFE1", "BFE2", "TFOG", "IFOG", "PLAY", "POSS", "SPOS", "VILE", "FIRE", "FATB", "FBXP", "SKEL",
+    "MANF", "FATT", "CPOS", "SARG", "HEAD", "BAL7", "BOSS", "BOS2", "SKUL", "SPID", "BSPI", "APLS",
+    "APBX", "CYBR", "PAIN", "SSWV", "KEEN", "BBRN", "BOSF", "ARM1", "ARM2", "BAR1", "BEXP", "FCAN",
+    "BON1", "BON2", "BKEY", "RKEY", "YKEY", "BSKU", "RSKU", "YSKU", "STIM", "MEDI", "SOUL", "PINV",
+    "PSTR", "PINS", "MEGA", "SUIT", "PMAP", "PVIS", "CLIP", "AMMO", "ROCK", "BROK", "CELL", "CELP",
+    "SHEL", "SBOX", "BPAK", "BFUG", "MGUN", "CSAW", "LAUN", "PLAS", "SHOT", "SGN2", "COLU", "SMT2",
+    "GOR1", "POL2", "POL5", "POL4", "POL3", "POL1", "POL6", "GOR2", "GOR3", "GOR4", "GOR5", "SMIT",
+    "COL1", "COL2", "COL3", "COL4", "CAND", "CBRA", "COL6", "TRE1", "TRE2", "ELEC", "CEYE", "FSKU",
+    "COL5", "TBLU", "TGRN", "TRED", "SMBT", "SMGT", "SMRT", "HDB1", "HDB2", "HDB3", "HDB4", "HDB5",
+    "HDB6", "POB1", "POB2", "BRS1", "TLMP", "TLP2",
+];
+const IWAD_FACE: &str = "STF";
 
-    fn to_fist_sprite(&self) -> String {
-        self.to_string() + "\\"
+pub fn sprite_collision<'a>(
+    sprites: &SpriteNames,
+    others: impl IntoIterator<Item = &'a SkinData>,
+) -> Option<String> {
+    if sprites.face == IWAD_FACE {
+        return Some("Clashes with Doom's status bar face".into());
     }
+    let heads = sprite_heads(sprites);
+    if let Some(sprite) = heads
+        .iter()
+        .find(|sprite| IWAD_SPRITES.contains(&sprite.as_str()))
+    {
+        return Some(format!("Clashes with Doom 2's {sprite} sprite"));
+    }
+    others.into_iter().find_map(|other| {
+        let other_heads = sprite_heads(&other.sprite_names().ok()?);
+        heads
+            .iter()
+            .any(|sprite| other_heads.contains(sprite))
+            .then(|| format!("Clashes with the sprites of '{}'", other.name))
+    })
+}
 
-    fn quoted(&self) -> String {
-        format!("\"{self}\"")
+fn sprite_heads(sprites: &SpriteNames) -> Vec<String> {
+    sprites
+        .to_lump_names()
+        .into_iter()
+        .map(|name| name.chars().take(4).collect())
+        .collect()
+}
+
+pub fn suggest_sprite_prefix<'a>(
+    name: &str,
+    others: impl IntoIterator<Item = &'a SkinData> + Clone,
+) -> String {
+    let words = name
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    let seed = match words.as_slice() {
+        [] => String::new(),
+        [word] => word.to_string(),
+        [first, second] => first[..1].to_string() + second,
+        words => words.iter().map(|word| &word[..1]).collect(),
+    };
+    unique_sprite_prefix(&seed, others)
+}
+
+pub fn unique_sprite_prefix<'a>(
+    seed: &str,
+    others: impl IntoIterator<Item = &'a SkinData> + Clone,
+) -> String {
+    let mut letters = seed
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_uppercase())
+        .chain(std::iter::repeat('A'))
+        .take(3)
+        .collect::<Vec<_>>();
+    for _ in 0..26 * 26 * 26 {
+        let candidate = letters.iter().collect::<String>();
+        if sprite_collision(&SpriteNames::from_prefix(&candidate), others.clone()).is_none() {
+            return candidate;
+        }
+        for letter in letters.iter_mut().rev() {
+            *letter = if *letter == 'Z' {
+                'A'
+            } else {
+                (*letter as u8 + 1) as char
+            };
+            if *letter != 'A' {
+                break;
+            }
+        }
     }
+    letters.iter().collect()
 }
 
 #[allow(non_camel_case_types)]
@@ -47,25 +203,207 @@ pub enum Format {
     // ZScriptPk3,
 }
 
-type Render = fn(&DynamicImage, &Path, &str, &mut Rendering, usize) -> anyhow::Result<()>;
-type Produce = fn(&Path, &Path, Vec<(String, String)>, crc: &Crc32) -> anyhow::Result<()>;
+#[derive(Default, Clone, Copy, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub enum FistStyle {
+    #[default]
+    Single,
+    Alternating,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub enum DamageRamp {
+    #[default]
+    Reciprocal,
+    Linear,
+    Flat,
+}
+
+impl DamageRamp {
+    pub fn strength(&self, level: u8) -> u8 {
+        match self {
+            DamageRamp::Reciprocal => 255 - 255 / (level + 1),
+            DamageRamp::Linear => level * 51,
+            DamageRamp::Flat => 0,
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub enum Backdrop {
+    #[default]
+    None,
+    Solid,
+    Gradient,
+}
+
+impl Backdrop {
+    pub fn color_at(&self, depth: f32) -> Option<[u8; 3]> {
+        match self {
+            Backdrop::None => None,
+            Backdrop::Solid => Some([64, 64, 64]),
+            Backdrop::Gradient => {
+                let shade = (96.0 - 64.0 * depth) as u8;
+                Some([shade, shade, shade])
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub fist_style: FistStyle,
+    #[serde(
+        serialize_with = "serialize_color",
+        deserialize_with = "deserialize_color"
+    )]
+    pub pain_color: [u8; 3],
+    pub pain_strength: u8,
+    pub damage_ramp: DamageRamp,
+    pub mugshot_expressions: bool,
+    pub mugshot_shoulders: bool,
+    pub mugshot_backdrop: Backdrop,
+    #[serde(skip)]
+    pub kept_renders_dir: Option<PathBuf>,
+    pub cache_dir: Option<PathBuf>,
+    #[serde(skip)]
+    pub jobs: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            fist_style: FistStyle::default(),
+            pain_color: [255, 0, 0],
+            pain_strength: 185,
+            damage_ramp: DamageRamp::default(),
+            mugshot_expressions: true,
+            mugshot_shoulders: false,
+            mugshot_backdrop: Backdrop::default(),
+            kept_renders_dir: None,
+            cache_dir: None,
+            jobs: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+impl Settings {
+    pub fn pain_tint(&self) -> Srgba {
+        self.tint(self.pain_strength)
+    }
+
+    pub fn damage_tint(&self, level: u8) -> Srgba {
+        self.tint(self.damage_ramp.strength(level))
+    }
+
+    pub fn default_cache_dir() -> PathBuf {
+        std::env::temp_dir().join("doom-crafter-cache")
+    }
+
+    fn render_options(&self) -> String {
+        format!(
+            "{:?} {:?} {} {:?} {} {} {:?}",
+            self.fist_style,
+            self.pain_color,
+            self.pain_strength,
+            self.damage_ramp,
+            self.mugshot_expressions,
+            self.mugshot_shoulders,
+            self.mugshot_backdrop
+        )
+    }
+
+    fn tint(&self, strength: u8) -> Srgba {
+        let [r, g, b] = self
+            .pain_color
+            .map(|c| 255 - ((255 - c as u32) * strength as u32 / 255) as u8);
+        Srgba::new(r, g, b, 255)
+    }
+}
+
+fn serialize_color<S: serde::Serializer>(
+    color: &[u8; 3],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let [r, g, b] = color;
+    serializer.serialize_str(&format!("#{r:02X}{g:02X}{b:02X}"))
+}
+
+fn deserialize_color<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<[u8; 3], D::Error> {
+    let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+    let hex = value
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6)
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| serde::de::Error::custom(format!("{value} is not a #RRGGBB colour")))?;
+    let [_, r, g, b] = hex.to_be_bytes();
+    Ok([r, g, b])
+}
+
+type Render =
+    fn(&DynamicImage, &Path, &SpriteNames, &mut Rendering, usize, &Settings) -> anyhow::Result<()>;
+type Produce = fn(
+    &Path,
+    &Path,
+    Vec<(String, SpriteNames)>,
+    &Settings,
+    &dyn Fn(String),
+) -> Result<(), ConvertError>;
+
+pub struct Progress {
+    pub completed: usize,
+    pub total: usize,
+    pub message: String,
+    pub warning: bool,
+}
+
+struct Reporter<'a> {
+    callback: &'a (dyn Fn(Progress) + Sync),
+    started: AtomicUsize,
+    total: usize,
+}
+
+impl Reporter<'_> {
+    fn start(&self, message: String) {
+        (self.callback)(Progress {
+            completed: self.started.fetch_add(1, Ordering::SeqCst),
+            total: self.total,
+            message,
+            warning: false,
+        });
+    }
+
+    fn warn(&self, message: String) {
+        (self.callback)(Progress {
+            completed: self.started.load(Ordering::SeqCst),
+            total: self.total,
+            message,
+            warning: true,
+        });
+    }
+
+    fn finish(&self) {
+        (self.callback)(Progress {
+            completed: self.total,
+            total: self.total,
+            message: "Done".into(),
+            warning: false,
+        });
+    }
+}
 
 impl Format {
-    fn methods(&self) -> (Vec<Render>, Produce) {
+    fn methods(&self) -> (Vec<(&'static str, Render)>, Produce) {
         use Format::*;
+        let skin = ("skin", render_skin_with_crouch as Render);
+        let mugshot = ("mugshot", render_mugshot as Render);
+        let fist = ("fist", render_fist as Render);
         match self {
-            S_SkinWad => (
-                vec![render_skin_with_crouch, render_mugshot],
-                produce_s_skin_wad,
-            ),
-            S_SkinAndFistWads => (
-                vec![render_skin_with_crouch, render_mugshot, render_fist],
-                produce_s_skin_and_fist_wads,
-            ),
-            DecorateWad => (
-                vec![render_skin_with_crouch, render_mugshot, render_fist],
-                produce_decorate_wad,
-            ),
+            S_SkinWad => (vec![skin, mugshot], produce_s_skin_wad),
+            S_SkinAndFistWads => (vec![skin, mugshot, fist], produce_s_skin_and_fist_wads),
+            DecorateWad => (vec![skin, mugshot, fist], produce_decorate_wad),
         }
     }
 }
@@ -75,11 +413,24 @@ pub struct SkinData {
     pub name: String,
     pub path: String,
     pub sprite_prefix: String,
+    #[serde(default)]
+    pub sprites: String,
 }
 
 impl SkinData {
     pub fn as_refs(&self) -> [&str; 3] {
-        [&self.name, &self.path, &self.sprite_prefix]
+        let sprites = match self.sprites.as_str() {
+            "" => &self.sprite_prefix,
+            sprites => sprites,
+        };
+        [&self.name, &self.path, sprites]
+    }
+
+    pub fn sprite_names(&self) -> anyhow::Result<SpriteNames> {
+        match self.sprites.as_str() {
+            "" => Ok(SpriteNames::from_prefix(&self.sprite_prefix)),
+            sprites => SpriteNames::parse(sprites),
+        }
     }
 }
 
@@ -95,80 +446,672 @@ pub fn get_acc() -> Option<PathBuf> {
 
 pub struct Rendering {
     pub viewport: Viewport,
-    pub context: HeadlessContext,
+    pub context: Option<GlContext>,
+    pub context_error: Option<anyhow::Error>,
     pub camera: Camera,
 }
 
 impl Rendering {
     const DEPTH: f32 = 35.0;
 
-    pub fn new() -> anyhow::Result<Self> {
-        const DEPTH: f32 = 35.0;
-
+    pub fn new() -> Self {
         let viewport = Viewport::new_at_origo(204, 128);
-        let context = HeadlessContext::new()?;
-        let camera = Camera::new_perspective(
+        let (context, context_error) = match headless_context() {
+            Ok(context) => (Some(context), None),
+            Err(e) => (None, Some(e)),
+        };
+
+        Self {
+            viewport,
+            context,
+            context_error,
+            camera: Self::front_camera(viewport),
+        }
+    }
+
+    /// Puts the camera back in front of the model, undoing the moves of an earlier render.
+    pub fn reset_camera(&mut self) {
+        self.camera = Self::front_camera(self.viewport);
+    }
+
+    fn front_camera(viewport: Viewport) -> Camera {
+        Camera::new_perspective(
             viewport,
-            Vec3::unit_z() * DEPTH,
+            Vec3::unit_z() * Self::DEPTH,
             Vec3::zero(),
             Vec3::unit_y(),
             degrees(60.0),
             0.1,
             100.0,
-        );
+        )
+    }
+}
 
+/// A headless GL context, built like three-d's `HeadlessContext` but without falling through to
+/// a winit event loop when there is no display, where winit panics instead of failing.
+#[derive(Clone)]
+pub struct GlContext {
+    context: Context,
+    _glutin_context: Rc<glutin::Context<glutin::PossiblyCurrent>>,
+}
+
+impl std::ops::Deref for GlContext {
+    type Target = Context;
+
+    fn deref(&self) -> &Context {
+        &self.context
+    }
+}
+
+impl GlContext {
+    fn new() -> anyhow::Result<Self> {
+        let builder = glutin::ContextBuilder::new();
+        let size = glutin::dpi::PhysicalSize::new(1, 1);
+        #[cfg(target_os = "linux")]
+        let context = {
+            use glutin::platform::unix::HeadlessContextExt;
+            match builder.clone().build_osmesa(size) {
+                Ok(context) => context,
+                Err(osmesa) if !is_main_thread() => anyhow::bail!(
+                    "OSMesa is unavailable ({osmesa}) and display contexts are only made on the main thread"
+                ),
+                Err(osmesa)
+                    if std::env::var_os("DISPLAY").is_none()
+                        && std::env::var_os("WAYLAND_DISPLAY").is_none() =>
+                {
+                    anyhow::bail!("OSMesa is unavailable ({osmesa}) and there is no display")
+                }
+                Err(_) => builder.build_headless(&glutin::event_loop::EventLoop::new(), size)?,
+            }
+        };
+        #[cfg(not(target_os = "linux"))]
+        let context = if is_main_thread() {
+            builder.build_headless(&glutin::event_loop::EventLoop::new(), size)?
+        } else {
+            anyhow::bail!("Display contexts are only made on the main thread")
+        };
+
+        // The context is only ever current on the thread that created it.
+        let context = unsafe { context.make_current() }.map_err(|(_, e)| e)?;
+        let gl = unsafe {
+            three_d::context::Context::from_loader_function(|name| {
+                context.get_proc_address(name) as *const _
+            })
+        };
         Ok(Self {
-            viewport,
-            context,
-            camera,
+            context: Context::from_gl_context(Arc::new(gl))?,
+            _glutin_context: Rc::new(context),
         })
     }
 }
 
-pub fn convert(data: &Vec<SkinData>, format: Format, produced_file: &Path) -> anyhow::Result<()> {
-    let mut rendering = Rendering::new()?;
-    let rendered_dir = tempdir().unwrap();
-    let (renders, produce) = format.methods();
-    let crc = Crc32::new();
+/// Each thread makes its context once and shares it between its `Rendering`s. OSMesa contexts
+/// can be made on any thread, but winit allows a single event loop per process, on the main
+/// thread, so elsewhere there is no fallback to a display context.
+fn headless_context() -> anyhow::Result<GlContext> {
+    thread_local! {
+        static CONTEXT: OnceCell<Result<GlContext, String>> = const { OnceCell::new() };
+    }
+    CONTEXT
+        .with(|context| {
+            context
+                .get_or_init(|| GlContext::new().map_err(|e| format!("{e:#}")))
+                .clone()
+        })
+        .map_err(anyhow::Error::msg)
+}
 
-    let mut names_and_sprite_prefixes = vec![];
-    for (
-        index,
-        SkinData {
-            name,
-            path,
-            sprite_prefix,
-        },
-    ) in data.iter().enumerate()
-    {
-        names_and_sprite_prefixes.push((name.clone(), sprite_prefix.clone()));
-        let image = image::open(path)?;
-        for render in &renders {
+fn is_main_thread() -> bool {
+    std::thread::current().name() == Some("main")
+}
+
+#[derive(Debug, Clone)]
+pub struct SkinRef {
+    pub index: usize,
+    pub name: String,
+}
+
+impl std::fmt::Display for SkinRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "skin {} '{}'", self.index, self.name)
+    }
+}
+
+#[derive(Debug)]
+pub enum ConvertError {
+    InvalidSkin {
+        skin: SkinRef,
+        source: anyhow::Error,
+    },
+    RenderFailed {
+        skin: SkinRef,
+        stage: &'static str,
+        source: anyhow::Error,
+    },
+    PackFailed {
+        skin: Option<SkinRef>,
+        source: anyhow::Error,
+    },
+    ExternalTool {
+        tool: String,
+        source: anyhow::Error,
+    },
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Cancelled,
+}
+
+impl ConvertError {
+    pub(crate) fn io(path: &Path) -> impl FnOnce(std::io::Error) -> Self {
+        let path = path.to_path_buf();
+        move |source| ConvertError::Io { path, source }
+    }
+}
+
+impl std::fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConvertError::InvalidSkin { skin, .. } => write!(f, "{skin} is not a valid skin image"),
+            ConvertError::RenderFailed { skin, stage, .. } => {
+                write!(f, "rendering {skin} failed in the {stage} stage")
+            }
+            ConvertError::PackFailed {
+                skin: Some(skin), ..
+            } => write!(f, "packing {skin} failed"),
+            ConvertError::PackFailed { skin: None, .. } => write!(f, "packing failed"),
+            ConvertError::ExternalTool { tool, .. } => write!(f, "{tool} failed"),
+            ConvertError::Io { path, .. } => write!(f, "could not access {}", path.display()),
+            ConvertError::Cancelled => write!(f, "conversion cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConvertError::InvalidSkin { source, .. }
+            | ConvertError::RenderFailed { source, .. }
+            | ConvertError::PackFailed { source, .. }
+            | ConvertError::ExternalTool { source, .. } => Some(source.as_ref()),
+            ConvertError::Io { source, .. } => Some(source),
+            ConvertError::Cancelled => None,
+        }
+    }
+}
+
+struct RenderQueue<'a> {
+    data: &'a [SkinData],
+    sprites: &'a [SpriteNames],
+    renders: &'a [(&'static str, Render)],
+    settings: &'a Settings,
+    rendered_dir: &'a Path,
+    reporter: &'a Reporter<'a>,
+    cancelled: &'a AtomicBool,
+    next_skin: AtomicUsize,
+    failed: AtomicBool,
+    fallback_reported: Once,
+}
+
+impl RenderQueue<'_> {
+    /// Renders skins until none are left, on a rendering of this thread's own, and hands each
+    /// finished skin to the grab threads.
+    fn render_all(&self, sender: &Sender<usize>) -> Result<(), ConvertError> {
+        let mut rendering = Rendering::new();
+        if let Some(e) = &rendering.context_error {
+            self.fallback_reported.call_once(|| {
+                self.reporter
+                    .warn(format!("No GL context, rendering on the CPU: {e:#}"))
+            });
+        }
+        loop {
+            if self.cancelled.load(Ordering::SeqCst) || self.failed.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+            let index = self.next_skin.fetch_add(1, Ordering::SeqCst);
+            if index >= self.data.len() {
+                return Ok(());
+            }
+            self.render_skin(&mut rendering, index)
+                .inspect_err(|_| self.failed.store(true, Ordering::SeqCst))?;
+            if !self.cancelled.load(Ordering::SeqCst) {
+                let _ = sender.send(index);
+            }
+        }
+    }
+
+    fn grab_all(&self, receiver: &Mutex<Receiver<usize>>, crc: &Crc32) -> Result<(), ConvertError> {
+        loop {
+            let Ok(index) = receiver.lock().unwrap().recv() else {
+                return Ok(());
+            };
+            self.reporter
+                .start(format!("Grabbing {}", self.data[index].name));
+            grab_renders(self.rendered_dir, index, crc).map_err(|source| {
+                self.failed.store(true, Ordering::SeqCst);
+                ConvertError::PackFailed {
+                    skin: Some(self.skin_ref(index)),
+                    source,
+                }
+            })?;
+        }
+    }
+
+    fn skin_ref(&self, index: usize) -> SkinRef {
+        SkinRef {
+            index,
+            name: self.data[index].name.clone(),
+        }
+    }
+
+    fn render_skin(&self, rendering: &mut Rendering, index: usize) -> Result<(), ConvertError> {
+        let SkinData { name, path, .. } = &self.data[index];
+        let sprites = &self.sprites[index];
+        let bytes = std::fs::read(path).map_err(ConvertError::io(Path::new(path)))?;
+        let image = decode_skin(&bytes).map_err(|source| ConvertError::InvalidSkin {
+            skin: self.skin_ref(index),
+            source,
+        })?;
+        for (label, render) in self.renders {
+            if self.cancelled.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+            self.reporter.start(format!("Rendering {name}: {label}"));
+            let cached =
+                self.settings.cache_dir.as_ref().map(|cache_dir| {
+                    cache_dir.join(cache_key(&bytes, sprites, self.settings, label))
+                });
+            if let Some(cached) = &cached
+                && cached.exists()
+            {
+                restore_cached(cached, self.rendered_dir, index)?;
+                continue;
+            }
+            // Every stage starts from the same camera, so it renders the same whether or not the
+            // stages before it came from the cache.
+            rendering.reset_camera();
+            let existing = index_subdirs(self.rendered_dir, index)?;
             render(
                 &image,
-                &rendered_dir.path(),
-                sprite_prefix,
-                &mut rendering,
+                self.rendered_dir,
+                sprites,
+                rendering,
                 index,
-            )?;
+                self.settings,
+            )
+            .map_err(|source| ConvertError::RenderFailed {
+                skin: self.skin_ref(index),
+                stage: label,
+                source,
+            })?;
+            if let Some(cached) = &cached {
+                store_cached(self.rendered_dir, &existing, cached, index)?;
+            }
         }
-        rendering.camera = Camera::new_perspective(
-            rendering.viewport,
-            Vec3::unit_z() * Rendering::DEPTH,
-            Vec3::zero(),
-            Vec3::unit_y(),
-            degrees(60.0),
-            0.1,
-            100.0,
-        );
+
+        if let Some(kept_renders_dir) = &self.settings.kept_renders_dir {
+            copy_renders(self.rendered_dir, &kept_renders_dir.join("rendered"), index)?;
+        }
+        Ok(())
+    }
+}
+
+fn sprite_names(data: &[SkinData]) -> Result<Vec<SpriteNames>, ConvertError> {
+    let mut sprites = vec![];
+    for (index, skin) in data.iter().enumerate() {
+        let invalid = |source| ConvertError::InvalidSkin {
+            skin: SkinRef {
+                index,
+                name: skin.name.clone(),
+            },
+            source,
+        };
+        let names = skin.sprite_names().map_err(invalid)?;
+        if let Some(collision) = sprite_collision(&names, &data[..index]) {
+            return Err(invalid(anyhow::anyhow!(
+                "sprites {}: {collision}",
+                names.skin
+            )));
+        }
+        sprites.push(names);
     }
+    Ok(sprites)
+}
 
+pub fn convert(
+    data: &[SkinData],
+    format: Format,
+    settings: &Settings,
+    produced_file: &Path,
+    progress: &(dyn Fn(Progress) + Sync),
+    cancelled: &AtomicBool,
+) -> Result<(), ConvertError> {
+    let sprites = sprite_names(data)?;
+    if let Some(kept_renders_dir) = &settings.kept_renders_dir {
+        clear_kept_renders(kept_renders_dir)?;
+    }
+    let temp_dir = std::env::temp_dir();
+    let rendered_dir = tempdir().map_err(ConvertError::io(&temp_dir))?;
+    let (renders, produce) = format.methods();
+    let crc = Crc32::new();
+    let reporter = Reporter {
+        callback: progress,
+        started: AtomicUsize::new(0),
+        total: data.len() * (renders.len() + 2) + 1,
+    };
+    let queue = RenderQueue {
+        data,
+        sprites: &sprites,
+        renders: &renders,
+        settings,
+        rendered_dir: rendered_dir.path(),
+        reporter: &reporter,
+        cancelled,
+        next_skin: AtomicUsize::new(0),
+        failed: AtomicBool::new(false),
+        fallback_reported: Once::new(),
+    };
+
+    // Each render thread takes the next skin with a rendering of its own, and hands it to the
+    // grab threads for the CPU-bound grab and crop once all its stages are done.
+    let (sender, receiver) = mpsc::channel::<usize>();
+    let receiver = Mutex::new(receiver);
+    let jobs = settings.jobs.max(1);
+    let (rendered, grabbed) = std::thread::scope(|scope| {
+        let (queue, crc, receiver) = (&queue, &crc, &receiver);
+        let grabbers = (0..jobs)
+            .map(|_| scope.spawn(move || queue.grab_all(receiver, crc)))
+            .collect::<Vec<_>>();
+        let renderers = (0..jobs)
+            .map(|_| {
+                let sender = sender.clone();
+                scope.spawn(move || queue.render_all(&sender))
+            })
+            .collect::<Vec<_>>();
+        drop(sender);
+        let rendered = renderers
+            .into_iter()
+            .try_for_each(|renderer| renderer.join().unwrap());
+        let grabbed = grabbers
+            .into_iter()
+            .try_for_each(|grabber| grabber.join().unwrap());
+        (rendered, grabbed)
+    });
+    rendered?;
+    grabbed?;
+    if cancelled.load(Ordering::SeqCst) {
+        return Err(ConvertError::Cancelled);
+    }
+
+    let names_and_sprites = data
+        .iter()
+        .map(|skin| skin.name.clone())
+        .zip(sprites.iter().cloned())
+        .collect();
     produce(
-        &rendered_dir.path(),
+        rendered_dir.path(),
         produced_file,
-        names_and_sprite_prefixes,
-        &crc,
+        names_and_sprites,
+        settings,
+        &|message| reporter.start(message),
     )?;
+    if let Some(kept_renders_dir) = &settings.kept_renders_dir {
+        for index in 0..data.len() {
+            copy_renders(
+                rendered_dir.path(),
+                &kept_renders_dir.join("grabbed"),
+                index,
+            )?;
+        }
+    }
+    reporter.finish();
+
+    Ok(())
+}
+
+/// Removes the renders kept by an earlier run so skins dropped from the list don't linger.
+fn clear_kept_renders(kept_renders_dir: &Path) -> Result<(), ConvertError> {
+    for stage in ["rendered", "grabbed"] {
+        let dir = kept_renders_dir.join(stage);
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).map_err(ConvertError::io(&dir))?;
+        }
+    }
+    Ok(())
+}
+
+fn copy_renders(rendered_dir: &Path, kept_dir: &Path, index: usize) -> Result<(), ConvertError> {
+    for subdir in index_subdirs(rendered_dir, index)? {
+        copy_files(&subdir, &kept_dir.join(subdir.file_name().unwrap()))?;
+    }
+    Ok(())
+}
+
+fn copy_files(from_dir: &Path, to_dir: &Path) -> Result<(), ConvertError> {
+    std::fs::create_dir_all(to_dir).map_err(ConvertError::io(to_dir))?;
+    for file in subdirs(from_dir)? {
+        std::fs::copy(&file, to_dir.join(file.file_name().unwrap()))
+            .map_err(ConvertError::io(&file))?;
+    }
+    Ok(())
+}
+
+fn subdirs(dir: &Path) -> Result<Vec<PathBuf>, ConvertError> {
+    let mut subdirs = vec![];
+    for entry in std::fs::read_dir(dir).map_err(ConvertError::io(dir))? {
+        subdirs.push(entry.map_err(ConvertError::io(dir))?.path());
+    }
+    Ok(subdirs)
+}
+
+fn index_subdirs(rendered_dir: &Path, index: usize) -> Result<Vec<PathBuf>, ConvertError> {
+    let index = index.to_string();
+    Ok(subdirs(rendered_dir)?
+        .into_iter()
+        .filter(|subdir| {
+            subdir
+                .file_name()
+                .and_then(|name| name.to_str()?.strip_suffix(&index))
+                .is_some_and(|name| !name.ends_with(|c: char| c.is_ascii_digit()))
+        })
+        .collect())
+}
 
+/// FNV-1a over the skin, its sprites and the render options, fixed so cache entries stay valid
+/// across toolchain updates.
+fn cache_key(image: &[u8], sprites: &SpriteNames, settings: &Settings, render: &str) -> String {
+    let sprites = sprites.to_lump_names().join(" ");
+    let options = settings.render_options();
+    let parts: [&[u8]; 5] = [
+        env!("CARGO_PKG_VERSION").as_bytes(),
+        image,
+        sprites.as_bytes(),
+        options.as_bytes(),
+        render.as_bytes(),
+    ];
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        for byte in (part.len() as u64).to_le_bytes().iter().chain(part) {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{render}-{hash:016x}")
+}
+
+fn restore_cached(cached: &Path, rendered_dir: &Path, index: usize) -> Result<(), ConvertError> {
+    for subdir in subdirs(cached)? {
+        let name = subdir.file_name().unwrap().to_string_lossy();
+        copy_files(&subdir, &rendered_dir.join(format!("{name}{index}")))?;
+    }
     Ok(())
 }
+
+fn store_cached(
+    rendered_dir: &Path,
+    existing: &[PathBuf],
+    cached: &Path,
+    index: usize,
+) -> Result<(), ConvertError> {
+    let cache_dir = cached.parent().unwrap();
+    std::fs::create_dir_all(cache_dir).map_err(ConvertError::io(cache_dir))?;
+    let partial = tempfile::Builder::new()
+        .prefix(".partial")
+        .tempdir_in(cache_dir)
+        .map_err(ConvertError::io(cache_dir))?;
+    for subdir in index_subdirs(rendered_dir, index)? {
+        if existing.contains(&subdir) {
+            continue;
+        }
+        let name = subdir.file_name().unwrap().to_string_lossy();
+        let name = name.strip_suffix(&index.to_string()).unwrap_or(&name);
+        copy_files(&subdir, &partial.path().join(name))?;
+    }
+    match std::fs::rename(partial.path(), cached) {
+        Err(_) if cached.exists() => Ok(()),
+        result => result.map_err(ConvertError::io(cached)),
+    }
+}
+
+pub fn preview(
+    data: &SkinData,
+    settings: &Settings,
+    sheet: &Path,
+    progress: &(dyn Fn(Progress) + Sync),
+    cancelled: &AtomicBool,
+) -> Result<(), ConvertError> {
+    let sprites = sprite_names(std::slice::from_ref(data))?;
+    let temp_dir = std::env::temp_dir();
+    let rendered_dir = tempdir().map_err(ConvertError::io(&temp_dir))?;
+    // The DECORATE format renders every stage the sheet shows.
+    let (renders, _) = Format::DecorateWad.methods();
+    let settings = Settings {
+        kept_renders_dir: None,
+        ..settings.clone()
+    };
+    let reporter = Reporter {
+        callback: progress,
+        started: AtomicUsize::new(0),
+        total: renders.len() + 1,
+    };
+    let queue = RenderQueue {
+        data: std::slice::from_ref(data),
+        sprites: &sprites,
+        renders: &renders,
+        settings: &settings,
+        rendered_dir: rendered_dir.path(),
+        reporter: &reporter,
+        cancelled,
+        next_skin: AtomicUsize::new(0),
+        failed: AtomicBool::new(false),
+        fallback_reported: Once::new(),
+    };
+    // Nothing is grabbed, the sheet lays out the renders as they are.
+    let (sender, _) = mpsc::channel();
+    queue.render_all(&sender)?;
+    if cancelled.load(Ordering::SeqCst) {
+        return Err(ConvertError::Cancelled);
+    }
+
+    reporter.start(format!("Writing {}", sheet.display()));
+    produce_contact_sheet(rendered_dir.path(), 0, sheet)
+        .map_err(|source| ConvertError::PackFailed { skin: None, source })?;
+    reporter.finish();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn skin(name: &str, sprite_prefix: &str) -> SkinData {
+        SkinData {
+            name: name.into(),
+            sprite_prefix: sprite_prefix.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn mugshot_lumps_are_the_status_bar_face_set() {
+        let lumps = SpriteNames::from_prefix("abc").to_mugshot_lumps();
+        assert_eq!(lumps.len(), 42);
+        for lump in [
+            "ABCDEAD0", "ABCGOD0", "ABCST00", "ABCST42", "ABCTL40", "ABCTR00", "ABCOUCH3",
+            "ABCEVL1", "ABCKILL4",
+        ] {
+            assert!(lumps.contains(&lump.to_string()), "{lump} is missing");
+        }
+        assert!(lumps.iter().all(|lump| lump.len() <= 8));
+        assert_eq!(lumps.iter().collect::<BTreeSet<_>>().len(), lumps.len());
+    }
+
+    #[test]
+    fn parse_reads_four_names() {
+        let sprites = SpriteNames::parse(" skin crch  FIST fac ").unwrap();
+        assert_eq!(
+            sprites,
+            SpriteNames {
+                skin: "SKIN".into(),
+                crouch: "CRCH".into(),
+                fist: "FIST".into(),
+                face: "FAC".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_rejects_malformed_names() {
+        for sprites in [
+            "",
+            "SKIN CRCH FIST",
+            "SKIN CRCH FIST FAC MORE",
+            "SKI CRCH FIST FAC",
+            "SKIN CRCH FIST FACE",
+            "SK-N CRCH FIST FAC",
+            "SKIN CRCH FIST F^C",
+        ] {
+            assert!(SpriteNames::parse(sprites).is_err(), "{sprites:?} parsed");
+        }
+    }
+
+    #[test]
+    fn parse_rejects_clashing_names() {
+        assert!(SpriteNames::parse("SKIN SKIN FIST FAC").is_err());
+        assert!(SpriteNames::parse("SKIN CRCH SKIN FAC").is_err());
+        // SKIDEAD0 would be read as a frame of the SKID sprite.
+        assert!(SpriteNames::parse("SKID CRCH FIST SKI").is_err());
+    }
+
+    #[test]
+    fn collisions_with_the_iwad_and_other_skins() {
+        assert!(sprite_collision(&SpriteNames::from_prefix("STF"), []).is_some());
+        // The PLA face set has PLAS and PLAY lumps, which are Doom 2 sprites.
+        assert!(sprite_collision(&SpriteNames::from_prefix("PLA"), []).is_some());
+        let others = [skin("steve", "STE")];
+        assert!(sprite_collision(&SpriteNames::from_prefix("ste"), &others).is_some());
+        assert!(sprite_collision(&SpriteNames::from_prefix("ALX"), &others).is_none());
+    }
+
+    #[test]
+    fn suggestions_come_from_the_name() {
+        assert_eq!(suggest_sprite_prefix("Steve", []), "STE");
+        assert_eq!(suggest_sprite_prefix("big boss", []), "BBO");
+        assert_eq!(suggest_sprite_prefix("Doom Guy Classic", []), "DGC");
+        assert_eq!(suggest_sprite_prefix("x", []), "XAA");
+        assert_eq!(suggest_sprite_prefix("42", []), "AAA");
+    }
+
+    #[test]
+    fn unique_prefixes_skip_taken_ones() {
+        assert_eq!(unique_sprite_prefix("PLA", []), "PLB");
+        // STF is the status bar face, so the next free prefix after STE is STG.
+        let others = [skin("steve", "STE")];
+        assert_eq!(unique_sprite_prefix("ste", &others), "STG");
+        let others = [skin("a", "AZZ")];
+        assert_eq!(unique_sprite_prefix("AZZ", &others), "BAA");
+    }
+}
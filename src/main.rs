@@ -1,22 +1,38 @@
 mod converting;
+mod previewing;
 mod producing;
+mod rasterizing;
 mod rendering;
+mod validating;
 
 use crate::converting::*;
+use crate::validating::{load_skin, validate_skin};
+use anyhow::Context as _;
 use color_eyre::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, poll};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers, poll};
 use csv::Reader;
 use ratatui::style::{Color, Modifier, Style, palette::tailwind};
 use ratatui::{
-    DefaultTerminal, Frame,
+    Frame, Terminal,
+    backend::CrosstermBackend,
     layout::{Constraint, Flex, Layout, Margin, Rect},
     style::Stylize,
     text::{Line, Text},
-    widgets::{Block, Cell, Clear, Paragraph, Row, Scrollbar, ScrollbarState, Table, TableState},
+    widgets::{
+        Block, Cell, Clear, Gauge, List, ListState, Paragraph, Row, Scrollbar, ScrollbarState,
+        Table, TableState,
+    },
 };
+use std::collections::BTreeSet;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+    mpsc::{self, Receiver, Sender},
+};
+use std::thread::JoinHandle;
 use std::time::Duration;
 use tui_prompts::prelude::*;
 
@@ -55,60 +71,128 @@ trait Context {
 struct MainContext {
     table: TableState,
     scroll: ScrollbarState,
+    preview: SpritePreview,
+    marked: BTreeSet<usize>,
 }
 
 impl MainContext {
     fn handle_event(&mut self, app: &mut App, event: Event) {
-        if let Event::Key(key) = event {
-            if key.kind == KeyEventKind::Press {
-                match key.code {
-                    KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
-                        app.subcontext = Some(Box::new(QuitConfirm));
-                    }
-                    KeyCode::Char('j') | KeyCode::Char('J') | KeyCode::Down => {
-                        self.table.select_next()
-                    }
-                    KeyCode::Char('k') | KeyCode::Char('K') | KeyCode::Up => {
-                        self.table.select_previous()
-                    }
-                    KeyCode::Char('a') | KeyCode::Char('A') => {
-                        app.subcontext = Some(Box::new(ItemPrompt::add()))
+        if let Event::Key(key) = event
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
+                    app.subcontext = Some(Box::new(QuitConfirm));
+                }
+                KeyCode::Up | KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                    self.move_items(&mut app.items, key.code == KeyCode::Up)
+                }
+                KeyCode::Char('j') | KeyCode::Char('J') | KeyCode::Down => self.table.select_next(),
+                KeyCode::Char('k') | KeyCode::Char('K') | KeyCode::Up => {
+                    self.table.select_previous()
+                }
+                KeyCode::Char(' ') => {
+                    if let Some(i) = self.table.selected()
+                        && !self.marked.remove(&i)
+                    {
+                        self.marked.insert(i);
                     }
-                    KeyCode::Char('e') | KeyCode::Char('E') => {
-                        if let Some(i) = self.table.selected() {
-                            app.subcontext = Some(Box::new(ItemPrompt::edit(&app.items[i], i)));
-                        }
+                }
+                KeyCode::Char('a') | KeyCode::Char('A') => {
+                    app.subcontext = Some(Box::new(ItemPrompt::add()))
+                }
+                KeyCode::Char('e') | KeyCode::Char('E') => {
+                    if let Some(i) = self.table.selected() {
+                        app.subcontext = Some(Box::new(ItemPrompt::edit(&app.items[i], i)));
                     }
-                    KeyCode::Char('d') | KeyCode::Char('D') => {
-                        if let Some(i) = self.table.selected() {
-                            app.items.remove(i);
+                }
+                KeyCode::Char('d') | KeyCode::Char('D') => {
+                    if !self.marked.is_empty() {
+                        for i in std::mem::take(&mut self.marked).into_iter().rev() {
+                            if i < app.items.len() {
+                                app.items.remove(i);
+                            }
                         }
+                    } else if let Some(i) = self.table.selected() {
+                        app.items.remove(i);
                     }
-                    KeyCode::Char('s') | KeyCode::Char('S') if !app.items.is_empty() => {
-                        app.subcontext = Some(Box::new(FilePrompt::save()))
-                    }
-                    KeyCode::Char('l') | KeyCode::Char('L') if app.items.is_empty() => {
-                        app.subcontext = Some(Box::new(FilePrompt::load()))
-                    }
-                    KeyCode::Enter if !app.items.is_empty() => {
-                        app.subcontext = Some(Box::new(ConvertPrompt::default()))
+                }
+                KeyCode::Char('s') | KeyCode::Char('S') if !app.items.is_empty() => {
+                    app.subcontext = Some(Box::new(FilePrompt::save()))
+                }
+                KeyCode::Char('l') | KeyCode::Char('L') if app.items.is_empty() => {
+                    app.subcontext = Some(Box::new(FilePrompt::load()))
+                }
+                KeyCode::Char('i') | KeyCode::Char('I') => {
+                    app.subcontext = Some(Box::new(ImportPrompt::new()))
+                }
+                KeyCode::Left => self.preview.rotate(false),
+                KeyCode::Right => self.preview.rotate(true),
+                KeyCode::Char('[') => self.preview.advance_frame(false),
+                KeyCode::Char(']') => self.preview.advance_frame(true),
+                KeyCode::Char('p') | KeyCode::Char('P') => {
+                    if let Some(i) = self.table.selected() {
+                        app.subcontext = Some(Box::new(PreviewPrompt::new(i)));
                     }
-                    _ => (),
                 }
+                KeyCode::Char('o') | KeyCode::Char('O') => {
+                    app.subcontext = Some(Box::new(SettingsPrompt::new(&app.settings)))
+                }
+                KeyCode::Enter if !app.items.is_empty() => {
+                    app.subcontext = Some(Box::new(ConvertPrompt::new(&app.settings)))
+                }
+                _ => (),
             }
         }
     }
 
-    fn draw(&mut self, theme: &Theme, items: &Vec<SkinData>, frame: &mut Frame) {
-        let vertical = &Layout::vertical([Constraint::Min(5), Constraint::Length(3)]);
-        let areas = vertical.split(frame.area());
+    fn move_items(&mut self, items: &mut [SkinData], up: bool) {
+        let indices = if self.marked.is_empty() {
+            self.table.selected().into_iter().collect::<Vec<_>>()
+        } else {
+            self.marked.iter().copied().collect()
+        };
+        let (Some(&first), Some(&last)) = (indices.first(), indices.last()) else {
+            return;
+        };
+        if up && first == 0 || !up && last + 1 >= items.len() {
+            return;
+        }
+
+        let moved = |i: usize| if up { i - 1 } else { i + 1 };
+        if up {
+            for &i in &indices {
+                items.swap(i - 1, i);
+            }
+        } else {
+            for &i in indices.iter().rev() {
+                items.swap(i, i + 1);
+            }
+        }
+        if let Some(selected) = self.table.selected()
+            && indices.contains(&selected)
+        {
+            self.table.select(Some(moved(selected)));
+        }
+        self.marked = self.marked.iter().map(|&i| moved(i)).collect();
+    }
 
-        self.draw_table(theme, items, frame, areas[0]);
-        self.draw_scrollbar(theme, frame, areas[0]);
+    fn draw(&mut self, theme: &Theme, items: &[SkinData], settings: &Settings, frame: &mut Frame) {
+        let vertical = &Layout::vertical([Constraint::Min(5), Constraint::Length(4)]);
+        let areas = vertical.split(frame.area());
+        let horizontal = &Layout::horizontal([Constraint::Min(30), Constraint::Length(34)]);
+        let top_areas = horizontal.split(areas[0]);
+
+        self.marked.retain(|&i| i < items.len());
+        self.draw_table(theme, items, frame, top_areas[0]);
+        self.draw_scrollbar(theme, frame, top_areas[0]);
+        let item = self.table.selected().and_then(|i| items.get(i));
+        self.preview
+            .draw(theme, item, settings, frame, top_areas[1]);
         self.draw_footer(theme, frame, areas[1]);
     }
 
-    fn draw_table(&mut self, theme: &Theme, items: &Vec<SkinData>, frame: &mut Frame, area: Rect) {
+    fn draw_table(&mut self, theme: &Theme, items: &[SkinData], frame: &mut Frame, area: Rect) {
         let header = ["Name", "Path", "Sprite"]
             .into_iter()
             .map(|h| {
@@ -121,13 +205,18 @@ impl MainContext {
             })
             .collect::<Row>()
             .height(1);
-        let rows = items.iter().map(|data| {
+        let rows = items.iter().enumerate().map(|(i, data)| {
+            let style = if self.marked.contains(&i) {
+                Style::default()
+                    .fg(theme.accent)
+                    .bg(theme.header_bg)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.fg).bg(theme.bg)
+            };
             data.as_refs()
                 .into_iter()
-                .map(|content| {
-                    Cell::from(Text::from(content))
-                        .style(Style::default().fg(theme.fg).bg(theme.bg))
-                })
+                .map(|content| Cell::from(Text::from(content)).style(style))
                 .collect::<Row>()
                 .height(1)
         });
@@ -136,7 +225,8 @@ impl MainContext {
             [
                 Constraint::Min(10),
                 Constraint::Min(10),
-                Constraint::Length(10),
+                // Wide enough for custom names such as "SKIN CRCH FIST FAC".
+                Constraint::Length(18),
             ],
         )
         .header(header)
@@ -168,9 +258,10 @@ impl MainContext {
 
     fn draw_footer(&mut self, theme: &Theme, frame: &mut Frame, area: Rect) {
         let info_footer = Paragraph::new(Text::from(
-            "(A) Add (E) Edit (D) Delete (S) Save (L) Load (J) Next (K) Prev (Enter) Convert (Q) Quit",
+            "(A) Add (I) Import (E) Edit (D) Delete (S) Save (L) Load (P) Preview (O) Options (J) Next (K) Prev (Space) Mark (Shift+Up/Down) Move (Left/Right) Rotate ([/]) Frame (Enter) Convert (Q) Quit",
         ))
         .centered()
+        .wrap(ratatui::widgets::Wrap { trim: true })
         .style(Style::default().fg(theme.accent).bg(theme.header_bg))
         .block(
             Block::bordered()
@@ -182,17 +273,182 @@ impl MainContext {
     }
 }
 
+type PreviewKey = (String, char, u8, Settings);
+
+struct SpritePreview {
+    frame: char,
+    rotation: u8,
+    key: Option<PreviewKey>,
+    image: Result<image::RgbaImage, String>,
+    renderer: Option<PreviewRenderer>,
+}
+
+type PreviewRenderer = (
+    Sender<PreviewKey>,
+    Receiver<(PreviewKey, Result<image::RgbaImage, String>)>,
+);
+
+impl Default for SpritePreview {
+    fn default() -> Self {
+        Self {
+            frame: 'A',
+            rotation: 0,
+            key: None,
+            image: Err(String::new()),
+            renderer: None,
+        }
+    }
+}
+
+impl SpritePreview {
+    fn rotate(&mut self, clockwise: bool) {
+        self.rotation = if clockwise {
+            (self.rotation + 1) % 8
+        } else {
+            (self.rotation + 7) % 8
+        };
+    }
+
+    fn advance_frame(&mut self, forward: bool) {
+        self.frame = match (self.frame, forward) {
+            ('W', true) => 'A',
+            ('A', false) => 'W',
+            (frame, true) => (frame as u8 + 1) as char,
+            (frame, false) => (frame as u8 - 1) as char,
+        };
+    }
+
+    fn update(&mut self, item: &SkinData, settings: &Settings) {
+        let rotation = if self.frame <= 'G' { self.rotation } else { 0 };
+        let key = (item.path.clone(), self.frame, rotation, settings.clone());
+        let (requests, results) = self.renderer.get_or_insert_with(spawn_preview_renderer);
+        for (rendered, image) in results.try_iter() {
+            if self.key.as_ref() == Some(&rendered) {
+                self.image = image;
+            }
+        }
+        if self.key.as_ref() == Some(&key) {
+            return;
+        }
+        self.key = Some(key.clone());
+        if requests.send(key).is_err() {
+            self.image = Err("the preview renderer stopped".into());
+        }
+    }
+
+    fn draw(
+        &mut self,
+        theme: &Theme,
+        item: Option<&SkinData>,
+        settings: &Settings,
+        frame: &mut Frame,
+        area: Rect,
+    ) {
+        let title = if self.frame <= 'G' {
+            format!("Preview {}{}", self.frame, self.rotation + 1)
+        } else {
+            format!("Preview {}0", self.frame)
+        };
+        let block = Block::bordered()
+            .title(title)
+            .border_style(Style::default().fg(theme.border))
+            .style(Style::default().bg(theme.bg));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let Some(item) = item else {
+            return;
+        };
+        self.update(item, settings);
+        let paragraph = match &self.image {
+            Ok(image) => Paragraph::new(half_blocks(image, inner, theme.bg)),
+            Err(e) => Paragraph::new(e.clone())
+                .wrap(ratatui::widgets::Wrap { trim: true })
+                .red(),
+        };
+        frame.render_widget(paragraph, inner);
+    }
+}
+
+/// Renders previews on a thread of its own so a slow render never holds up drawing.
+fn spawn_preview_renderer() -> PreviewRenderer {
+    let (request_sender, requests) = mpsc::channel::<PreviewKey>();
+    let (result_sender, results) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut rendering = None;
+        while let Ok(key) = requests.recv() {
+            let key = requests.try_iter().last().unwrap_or(key);
+            let rendering = rendering.get_or_insert_with(Rendering::new);
+            let (path, frame, rotation, settings) = &key;
+            let image = load_skin(Path::new(path))
+                .and_then(|atlas| {
+                    crate::rendering::render_sprite(&atlas, rendering, *frame, *rotation, settings)
+                })
+                .map_err(|e| e.to_string());
+            if result_sender.send((key, image)).is_err() {
+                return;
+            }
+        }
+    });
+    (request_sender, results)
+}
+
+fn half_blocks(image: &image::RgbaImage, area: Rect, background: Color) -> Vec<Line<'static>> {
+    let opaque = image.enumerate_pixels().filter(|(_, _, p)| p[3] > 0);
+    let Some((left, top, right, bottom)) = opaque.fold(None, |bounds, (x, y, _)| match bounds {
+        None => Some((x, y, x, y)),
+        Some((l, t, r, b)) => Some((x.min(l), y.min(t), x.max(r), y.max(b))),
+    }) else {
+        return vec![];
+    };
+    let sprite =
+        image::imageops::crop_imm(image, left, top, right - left + 1, bottom - top + 1).to_image();
+    let (width, height) = (sprite.width() as f32, sprite.height() as f32);
+    let scale = (area.width as f32 / width).min(area.height as f32 * 2.0 / height);
+    let sprite = image::imageops::resize(
+        &sprite,
+        ((width * scale) as u32).max(1),
+        ((height * scale) as u32).max(1),
+        image::imageops::FilterType::Nearest,
+    );
+
+    let color = |x: u32, y: u32| {
+        if y >= sprite.height() {
+            return background;
+        }
+        let [r, g, b, a] = sprite.get_pixel(x, y).0;
+        if a == 0 {
+            background
+        } else {
+            Color::Rgb(r, g, b)
+        }
+    };
+    (0..sprite.height())
+        .step_by(2)
+        .map(|y| {
+            (0..sprite.width())
+                .map(|x| {
+                    ratatui::text::Span::styled(
+                        "\u{2580}",
+                        Style::default().fg(color(x, y)).bg(color(x, y + 1)),
+                    )
+                })
+                .collect::<Line>()
+        })
+        .collect()
+}
+
 struct QuitConfirm;
 
 impl Context for QuitConfirm {
     fn handle_event(self: Box<Self>, app: &mut App, event: Event) -> Option<Box<dyn Context>> {
-        if let Event::Key(key) = event {
-            if key.kind == KeyEventKind::Press {
-                match key.code {
-                    KeyCode::Char('y') | KeyCode::Char('Y') => app.quit = true,
-                    KeyCode::Char('n') | KeyCode::Char('N') => return None,
-                    _ => return Some(self),
-                }
+        if let Event::Key(key) = event
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => app.quit = true,
+                KeyCode::Char('n') | KeyCode::Char('N') => return None,
+                _ => return Some(self),
             }
         }
         Some(self)
@@ -216,10 +472,14 @@ struct ItemPrompt {
     name: TextState<'static>,
     path: TextState<'static>,
     sprite: TextState<'static>,
+    sprites: TextState<'static>,
     item_field: ItemField,
     name_error: String,
     path_error: String,
+    path_warning: String,
     sprite_error: String,
+    sprites_error: String,
+    sprite_edited: bool,
     edit: Option<usize>,
 }
 
@@ -227,13 +487,39 @@ impl Context for ItemPrompt {
     fn handle_event(mut self: Box<Self>, app: &mut App, event: Event) -> Option<Box<dyn Context>> {
         if let Event::Key(key) = event {
             match key.code {
-                KeyCode::Tab => self.advance_field(),
+                KeyCode::Tab => {
+                    if self.item_field == ItemField::Path
+                        && let Some(path) = complete_path(self.path.value(), &[".png"])
+                    {
+                        set_value(&mut self.path, &path);
+                        self.validate_path();
+                    } else {
+                        self.advance_field();
+                    }
+                }
                 KeyCode::BackTab => self.retreat_field(),
                 KeyCode::Esc => return None,
+                KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    let path = self.path.value().to_string();
+                    return Some(Box::new(FileBrowser::new(self, &path, &[".png"])));
+                }
+                KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    let sprite = match self.sprite.value() {
+                        "" => suggest_sprite_prefix(self.name.value(), self.others(app)),
+                        sprite => unique_sprite_prefix(sprite, self.others(app)),
+                    };
+                    self.set_sprite(&sprite, app);
+                }
                 KeyCode::Enter => {
+                    let shown_warning = self.path_warning.clone();
+                    self.validate_skin();
+                    self.validate_sprites(app);
+                    self.validate_sprite(app);
                     if self.name.status().is_done()
                         && self.path.status().is_done()
                         && self.sprite.status().is_done()
+                        && self.sprites.status().is_done()
+                        && self.path_warning == shown_warning
                     {
                         self.submit_item_prompt(app);
                         return None;
@@ -241,7 +527,15 @@ impl Context for ItemPrompt {
                 }
                 _ => {
                     self.field().handle_key_event(key);
-                    self.validate_field();
+                    self.validate_field(app);
+                    match self.item_field {
+                        ItemField::Name if !self.sprite_edited && !self.name.value().is_empty() => {
+                            let sprite = suggest_sprite_prefix(self.name.value(), self.others(app));
+                            self.set_sprite(&sprite, app);
+                        }
+                        ItemField::Sprite => self.sprite_edited = true,
+                        _ => {}
+                    }
                 }
             }
         }
@@ -256,26 +550,40 @@ impl Context for ItemPrompt {
         frame.render_widget(popup, areas[0]);
         frame.render_widget(Clear, areas[1]);
         frame.render_widget(
-            Line::from("(Tab) Next (Enter) Submit").right_aligned(),
+            Line::from("(Tab) Next (Ctrl+O) Browse (Ctrl+G) Generate sprite (Enter) Submit")
+                .right_aligned(),
             areas[1],
         );
 
-        let areas = Layout::vertical(vec![Constraint::Length(1); 6])
+        let areas = Layout::vertical(vec![Constraint::Length(1); 8])
             .margin(2)
             .split(areas[0]);
         TextPrompt::from("Name").draw(frame, areas[0], &mut self.name);
         TextPrompt::from("Path").draw(frame, areas[2], &mut self.path);
         TextPrompt::from("Sprite").draw(frame, areas[4], &mut self.sprite);
+        TextPrompt::from("Sprite names (optional)").draw(frame, areas[6], &mut self.sprites);
 
         frame.render_widget(Line::from(self.name_error.clone()).red(), areas[1]);
-        frame.render_widget(Line::from(self.path_error.clone()).red(), areas[3]);
+        if self.path_error.is_empty() {
+            frame.render_widget(Line::from(self.path_warning.clone()).yellow(), areas[3]);
+        } else {
+            frame.render_widget(Line::from(self.path_error.clone()).red(), areas[3]);
+        }
         frame.render_widget(Line::from(self.sprite_error.clone()).red(), areas[5]);
+        if self.sprites_error.is_empty() && self.item_field == ItemField::Sprites {
+            frame.render_widget(
+                Line::from("Skin, crouch and fist sprites of 4 characters, face of 3").dark_gray(),
+                areas[7],
+            );
+        } else {
+            frame.render_widget(Line::from(self.sprites_error.clone()).red(), areas[7]);
+        }
     }
 }
 
-fn new_text_state(value: &String) -> TextState<'static> {
+fn new_text_state(value: &str) -> TextState<'static> {
     let mut state = TextState::default()
-        .with_value(value.clone())
+        .with_value(value.to_string())
         .with_status(Status::Done);
     *state.position_mut() = state.value().len();
     state.cursor_mut().0 += state.value().len() as u16;
@@ -295,6 +603,8 @@ impl ItemPrompt {
             name: new_text_state(&item.name).with_focus(FocusState::Focused),
             path: new_text_state(&item.path),
             sprite: new_text_state(&item.sprite_prefix),
+            sprites: new_text_state(&item.sprites),
+            sprite_edited: true,
             edit: Some(index),
             ..Default::default()
         }
@@ -305,6 +615,7 @@ impl ItemPrompt {
             ItemField::Name => &mut self.name,
             ItemField::Path => &mut self.path,
             ItemField::Sprite => &mut self.sprite,
+            ItemField::Sprites => &mut self.sprites,
         }
     }
 
@@ -313,7 +624,8 @@ impl ItemPrompt {
         self.item_field = match self.item_field {
             ItemField::Name => ItemField::Path,
             ItemField::Path => ItemField::Sprite,
-            ItemField::Sprite => ItemField::Name,
+            ItemField::Sprite => ItemField::Sprites,
+            ItemField::Sprites => ItemField::Name,
         };
         self.field().focus();
     }
@@ -321,14 +633,15 @@ impl ItemPrompt {
     fn retreat_field(&mut self) {
         self.field().blur();
         self.item_field = match self.item_field {
-            ItemField::Name => ItemField::Sprite,
+            ItemField::Name => ItemField::Sprites,
             ItemField::Path => ItemField::Name,
             ItemField::Sprite => ItemField::Path,
+            ItemField::Sprites => ItemField::Sprite,
         };
         self.field().focus();
     }
 
-    fn validate_field(&mut self) {
+    fn validate_field(&mut self, app: &App) {
         match self.item_field {
             ItemField::Name => {
                 *self.name.status_mut() = Status::Aborted;
@@ -339,39 +652,114 @@ impl ItemPrompt {
                     self.name_error.clear();
                 }
             }
-            ItemField::Path => {
-                *self.path.status_mut() = Status::Aborted;
-                let path = self.path.value();
-                if !path.ends_with(".png") {
-                    self.path_error = "Must be a png file!".into();
-                } else if !Path::new(path).exists() {
-                    self.path_error = "Does not exist!".into();
-                } else {
-                    *self.path.status_mut() = Status::Done;
-                    self.path_error.clear();
-                }
-            }
-            ItemField::Sprite => {
-                *self.sprite.status_mut() = Status::Aborted;
-                let sprite = self.sprite.value();
-                if sprite.len() != 3 {
-                    self.sprite_error = "Must be 3 characters long!".into();
-                } else if !validate_sprite(sprite) {
-                    self.sprite_error =
-                        "Must only contain alphabetic characters or ('[', ']', '\\')".into();
+            ItemField::Path => self.validate_path(),
+            ItemField::Sprite => self.validate_sprite(app),
+            ItemField::Sprites => {
+                self.validate_sprites(app);
+                self.validate_sprite(app);
+            }
+        }
+    }
+
+    fn validate_path(&mut self) {
+        *self.path.status_mut() = Status::Aborted;
+        self.path_warning.clear();
+        let path = self.path.value();
+        if !path.ends_with(".png") {
+            self.path_error = "Must be a png file!".into();
+        } else if !Path::new(path).exists() {
+            self.path_error = "Does not exist!".into();
+        } else {
+            *self.path.status_mut() = Status::Done;
+            self.path_error.clear();
+        }
+    }
+
+    fn validate_sprite(&mut self, app: &App) {
+        *self.sprite.status_mut() = Status::Aborted;
+        let sprite = self.sprite.value();
+        if !self.sprites.value().is_empty() {
+            // Custom sprite names replace everything the prefix would be used for.
+            *self.sprite.status_mut() = Status::Done;
+            self.sprite_error.clear();
+        } else if sprite.len() != 3 {
+            self.sprite_error = "Must be 3 characters long!".into();
+        } else if !validate_sprite(sprite) {
+            self.sprite_error =
+                "Must only contain alphabetic characters or ('[', ']', '\\')".into();
+        } else if let Some(collision) =
+            sprite_collision(&SpriteNames::from_prefix(sprite), self.others(app))
+        {
+            self.sprite_error = format!(
+                "{collision}, try {}!",
+                unique_sprite_prefix(sprite, self.others(app))
+            );
+        } else {
+            *self.sprite.status_mut() = Status::Done;
+            self.sprite_error.clear();
+        }
+    }
+
+    fn validate_sprites(&mut self, app: &App) {
+        *self.sprites.status_mut() = Status::Aborted;
+        let sprites = self.sprites.value();
+        if sprites.is_empty() {
+            *self.sprites.status_mut() = Status::Done;
+            self.sprites_error.clear();
+            return;
+        }
+        match SpriteNames::parse(sprites) {
+            Err(e) => self.sprites_error = format!("{e}!"),
+            Ok(sprites) => {
+                if let Some(collision) = sprite_collision(&sprites, self.others(app)) {
+                    self.sprites_error = format!("{collision}!");
                 } else {
-                    *self.sprite.status_mut() = Status::Done;
-                    self.sprite_error.clear();
+                    *self.sprites.status_mut() = Status::Done;
+                    self.sprites_error.clear();
                 }
             }
         }
     }
 
+    fn set_sprite(&mut self, sprite: &str, app: &App) {
+        set_value(&mut self.sprite, sprite);
+        self.validate_sprite(app);
+    }
+
+    fn others<'a>(&self, app: &'a App) -> impl Iterator<Item = &'a SkinData> + Clone {
+        let edit = self.edit;
+        app.items
+            .iter()
+            .enumerate()
+            .filter(move |(index, _)| Some(*index) != edit)
+            .map(|(_, item)| item)
+    }
+
+    fn validate_skin(&mut self) {
+        if !self.path.status().is_done() {
+            return;
+        }
+        match validate_skin(Path::new(self.path.value())) {
+            Ok(warnings) => {
+                self.path_warning = warnings
+                    .into_iter()
+                    .map(|warning| warning + "!")
+                    .collect::<Vec<_>>()
+                    .join(" ");
+            }
+            Err(e) => {
+                *self.path.status_mut() = Status::Aborted;
+                self.path_error = format!("{e}!");
+            }
+        }
+    }
+
     fn submit_item_prompt(&mut self, app: &mut App) {
         let item = SkinData {
             name: self.name.value().into(),
             path: self.path.value().into(),
             sprite_prefix: self.sprite.value().to_uppercase(),
+            sprites: self.sprites.value().to_uppercase(),
         };
         if let Some(index) = self.edit {
             let _ = std::mem::replace(&mut app.items[index], item);
@@ -381,6 +769,14 @@ impl ItemPrompt {
     }
 }
 
+impl PathPicker for ItemPrompt {
+    fn pick_path(&mut self, path: &str, _app: &App) {
+        set_value(&mut self.path, path);
+        self.validate_path();
+        self.validate_skin();
+    }
+}
+
 struct FilePrompt {
     save: bool,
     file_name: TextState<'static>,
@@ -392,14 +788,26 @@ impl Context for FilePrompt {
         if let Event::Key(key) = event {
             match key.code {
                 KeyCode::Esc => return None,
+                KeyCode::Tab => {
+                    if let Some(file_name) = complete_path(self.file_name.value(), &[".csv"]) {
+                        set_value(&mut self.file_name, &file_name);
+                        self.validate();
+                    }
+                }
+                KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    let file_name = self.file_name.value().to_string();
+                    return Some(Box::new(FileBrowser::new(self, &file_name, &[".csv"])));
+                }
                 KeyCode::Enter => {
                     if self.file_name.status().is_done() {
-                        if self.save {
-                            self.save_csv(app);
+                        let result = if self.save {
+                            self.save_csv(app)
                         } else {
-                            self.load_csv(app);
-                        }
-                        return None;
+                            self.load_csv(app)
+                        };
+                        return result
+                            .err()
+                            .map(|e| Box::new(ErrorPopup::new(e)) as Box<dyn Context>);
                     }
                 }
                 _ => {
@@ -420,9 +828,9 @@ impl Context for FilePrompt {
         frame.render_widget(Clear, areas[1]);
         frame.render_widget(
             Line::from(if self.save {
-                "(Enter) Save"
+                "(Tab) Complete (Ctrl+O) Browse (Enter) Save"
             } else {
-                "(Enter) Load"
+                "(Tab) Complete (Ctrl+O) Browse (Enter) Load"
             })
             .right_aligned(),
             areas[1],
@@ -453,33 +861,42 @@ impl FilePrompt {
         }
     }
 
-    fn save_csv(&self, app: &App) {
+    fn save_csv(&self, app: &App) -> anyhow::Result<()> {
         let file_name = self.file_name.value();
-
-        if Path::new(file_name).exists() {
-            std::fs::remove_file(file_name).unwrap();
-        }
-
         let mut writer = csv::Writer::from_writer(
             std::fs::OpenOptions::new()
                 .create(true)
+                .truncate(true)
                 .write(true)
                 .open(file_name)
-                .unwrap(),
+                .with_context(|| format!("saving {file_name}"))?,
         );
 
         for item in &app.items {
-            writer.serialize(item.clone()).unwrap();
+            writer
+                .serialize(item.clone())
+                .with_context(|| format!("saving {file_name}"))?;
         }
+        writer.flush()?;
+        write_settings(Path::new(file_name), &app.settings)
+            .with_context(|| format!("saving {file_name}"))
     }
 
-    fn load_csv(&self, app: &mut App) {
+    fn load_csv(&self, app: &mut App) -> anyhow::Result<()> {
         let file_name = self.file_name.value();
-        let mut reader = Reader::from_reader(BufReader::new(File::open(file_name).unwrap()));
-
-        for result in reader.deserialize() {
-            app.items.push(result.unwrap());
+        let skins =
+            read_skins(Path::new(file_name)).with_context(|| format!("loading {file_name}"))?;
+        if let Some(settings) =
+            read_settings(Path::new(file_name)).with_context(|| format!("loading {file_name}"))?
+        {
+            app.settings = Settings {
+                kept_renders_dir: app.settings.kept_renders_dir.take(),
+                jobs: app.settings.jobs,
+                ..settings
+            };
         }
+        app.items.extend(skins);
+        Ok(())
     }
 
     fn validate(&mut self) {
@@ -496,34 +913,42 @@ impl FilePrompt {
     }
 }
 
-struct ConvertPrompt {
-    file_name: TextState<'static>,
+struct ImportPrompt {
+    dir: TextState<'static>,
     error: String,
 }
 
-impl Default for ConvertPrompt {
-    fn default() -> Self {
-        Self {
-            file_name: TextState::default().with_focus(FocusState::Focused),
-            error: String::new(),
-        }
-    }
-}
-
-impl Context for ConvertPrompt {
-    fn handle_event(mut self: Box<Self>, _app: &mut App, event: Event) -> Option<Box<dyn Context>> {
+impl Context for ImportPrompt {
+    fn handle_event(mut self: Box<Self>, app: &mut App, event: Event) -> Option<Box<dyn Context>> {
         if let Event::Key(key) = event {
             match key.code {
                 KeyCode::Esc => return None,
+                KeyCode::Tab => {
+                    if let Some(dir) = complete_path(self.dir.value(), &[]) {
+                        set_value(&mut self.dir, &dir);
+                        self.validate();
+                    }
+                }
+                KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    let dir = self.dir.value().to_string();
+                    return Some(Box::new(FileBrowser::new(self, &dir, &[])));
+                }
                 KeyCode::Enter => {
-                    if self.file_name.status().is_done() {
-                        return Some(Box::new(GenerationPrompt::new(
-                            self.file_name.value().to_string(),
-                        )));
+                    if self.dir.status().is_done() {
+                        let dir = self.dir.value();
+                        return Some(match import_skins(Path::new(dir), &app.items) {
+                            Ok((skins, report)) => {
+                                app.items.extend(skins);
+                                Box::new(ImportReport { report })
+                            }
+                            Err(e) => {
+                                Box::new(ErrorPopup::new(e.context(format!("importing {dir}"))))
+                            }
+                        });
                     }
                 }
                 _ => {
-                    self.file_name.handle_key_event(key);
+                    self.dir.handle_key_event(key);
                     self.validate();
                 }
             }
@@ -538,115 +963,1002 @@ impl Context for ConvertPrompt {
         frame.render_widget(Clear, areas[0]);
         frame.render_widget(popup, areas[0]);
         frame.render_widget(Clear, areas[1]);
-        frame.render_widget(Line::from("(Enter) Submit").right_aligned(), areas[1]);
+        frame.render_widget(
+            Line::from("(Tab) Complete (Ctrl+O) Browse (Enter) Import").right_aligned(),
+            areas[1],
+        );
 
         let areas = Layout::vertical(vec![Constraint::Length(1); 2])
             .margin(2)
             .split(areas[0]);
-        TextPrompt::from("File name").draw(frame, areas[0], &mut self.file_name);
-        frame.render_widget(Line::from(self.error.clone()), areas[1]);
+        TextPrompt::from("Folder").draw(frame, areas[0], &mut self.dir);
+        frame.render_widget(Line::from(self.error.clone()).red(), areas[1]);
     }
 }
 
-impl ConvertPrompt {
+impl ImportPrompt {
+    fn new() -> Self {
+        Self {
+            dir: TextState::default().with_focus(FocusState::Focused),
+            error: String::new(),
+        }
+    }
+
     fn validate(&mut self) {
-        *self.file_name.status_mut() = Status::Aborted;
-        if !self.file_name.value().ends_with(".wad") {
-            self.error = "Must be a wad file!".into();
+        *self.dir.status_mut() = Status::Aborted;
+        if !Path::new(self.dir.value()).is_dir() {
+            self.error = "Must be a folder!".into();
         } else {
-            *self.file_name.status_mut() = Status::Done;
+            *self.dir.status_mut() = Status::Done;
             self.error.clear();
         }
     }
 }
 
-struct GenerationPrompt {
-    file_name: String,
+impl PathPicker for ImportPrompt {
+    fn pick_path(&mut self, path: &str, _app: &App) {
+        set_value(&mut self.dir, path);
+        self.validate();
+    }
 }
 
-impl Context for GenerationPrompt {
-    fn handle_event(self: Box<Self>, _app: &mut App, event: Event) -> Option<Box<dyn Context>> {
-        if let Event::Key(key) = event {
+trait PathPicker: Context {
+    fn pick_path(&mut self, path: &str, app: &App);
+}
+
+struct FileBrowser<P> {
+    parent: Box<P>,
+    dir: PathBuf,
+    extensions: &'static [&'static str],
+    entries: Vec<(String, bool)>,
+    list: ListState,
+    error: String,
+}
+
+impl<P: PathPicker + 'static> Context for FileBrowser<P> {
+    fn handle_event(mut self: Box<Self>, app: &mut App, event: Event) -> Option<Box<dyn Context>> {
+        if let Event::Key(key) = event
+            && key.kind == KeyEventKind::Press
+        {
             match key.code {
-                KeyCode::Esc => return None,
-                KeyCode::Char('d') | KeyCode::Char('D') => {
-                    return Some(Box::new(Converting::new(self.file_name, None, false)));
+                KeyCode::Esc => return Some(self.parent),
+                KeyCode::Char('j') | KeyCode::Char('J') | KeyCode::Down => self.list.select_next(),
+                KeyCode::Char('k') | KeyCode::Char('K') | KeyCode::Up => {
+                    self.list.select_previous()
                 }
-                KeyCode::Char('s') | KeyCode::Char('S') => {
-                    if let Some(acc) = get_acc() {
-                        return Some(Box::new(FistConfirm::new(self.file_name, acc)));
-                    } else {
-                        return Some(Box::new(Converting::new(self.file_name, None, true)));
+                KeyCode::Backspace | KeyCode::Left => self.open(".."),
+                KeyCode::Char(' ') if self.extensions.is_empty() => {
+                    let dir = self.dir.clone();
+                    self.pick(&dir, app);
+                    return Some(self.parent);
+                }
+                KeyCode::Enter | KeyCode::Right => {
+                    if let Some((name, is_dir)) = self
+                        .list
+                        .selected()
+                        .and_then(|i| self.entries.get(i))
+                        .cloned()
+                    {
+                        if is_dir {
+                            self.open(&name);
+                        } else {
+                            let path = self.dir.join(name);
+                            self.pick(&path, app);
+                            return Some(self.parent);
+                        }
                     }
                 }
-                _ => (),
+                _ => {}
             }
         }
         Some(self)
     }
 
-    fn draw(&mut self, _theme: &Theme, frame: &mut Frame) {
-        let popup = Block::bordered();
+    fn draw(&mut self, theme: &Theme, frame: &mut Frame) {
+        self.parent.draw(theme, frame);
         let areas = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
-            .split(popup_area(frame.area(), 70, 70));
-        let mut text_area = areas[0].clone();
-        text_area.y += text_area.height / 2;
+            .split(popup_area(frame.area(), 60, 60));
+        let popup = Block::bordered().title(self.dir.to_string_lossy().to_string());
+        let items = self.entries.iter().map(|(name, is_dir)| {
+            if *is_dir {
+                Line::from(format!("{name}/")).fg(theme.accent)
+            } else {
+                Line::from(name.clone())
+            }
+        });
         frame.render_widget(Clear, areas[0]);
+        if self.error.is_empty() {
+            frame.render_stateful_widget(
+                List::new(items)
+                    .block(popup)
+                    .highlight_style(Style::default().fg(theme.selected_fg).bg(theme.selected_bg)),
+                areas[0],
+                &mut self.list,
+            );
+        } else {
+            frame.render_widget(
+                Paragraph::new(Line::from(self.error.clone()).red()).block(popup),
+                areas[0],
+            );
+        }
         frame.render_widget(Clear, areas[1]);
-        frame.render_widget(popup, areas[0]);
-        frame.render_widget(
-            Paragraph::new("Would you like to generate using DECORATE or S_SKIN?").centered(),
-            text_area,
-        );
         frame.render_widget(
-            Line::from("(D) DECORATE (S) S_SKIN").right_aligned(),
+            Line::from(if self.extensions.is_empty() {
+                "(Enter) Open (Backspace) Up (Space) Select folder (Esc) Cancel"
+            } else {
+                "(Enter) Open (Backspace) Up (Esc) Cancel"
+            })
+            .right_aligned(),
             areas[1],
         );
     }
 }
 
-impl GenerationPrompt {
-    fn new(file_name: String) -> Self {
-        Self { file_name }
+impl<P: PathPicker> FileBrowser<P> {
+    fn pick(&mut self, path: &Path, app: &App) {
+        let path = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| path.strip_prefix(cwd).ok())
+            .filter(|path| !path.as_os_str().is_empty())
+            .unwrap_or(path);
+        self.parent.pick_path(&path.to_string_lossy(), app);
     }
 }
 
-struct FistConfirm {
-    file_name: String,
-    acc: PathBuf,
-}
+impl<P> FileBrowser<P> {
+    fn new(parent: Box<P>, current: &str, extensions: &'static [&'static str]) -> Self {
+        let current = Path::new(current);
+        let dir = if current.is_dir() {
+            current
+        } else {
+            current
+                .parent()
+                .filter(|dir| dir.is_dir())
+                .unwrap_or(Path::new("."))
+        };
+        let mut browser = Self {
+            parent,
+            dir: std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf()),
+            extensions,
+            entries: vec![],
+            list: ListState::default(),
+            error: String::new(),
+        };
+        browser.read_dir();
+        browser
+    }
 
-impl Context for FistConfirm {
-    fn handle_event(self: Box<Self>, _app: &mut App, event: Event) -> Option<Box<dyn Context>> {
-        if let Event::Key(key) = event {
-            match key.code {
-                KeyCode::Esc => return None,
-                KeyCode::Char('y') | KeyCode::Char('Y') => {
-                    return Some(Box::new(Converting::new(
-                        self.file_name,
-                        Some(self.acc),
-                        true,
-                    )));
-                }
-                KeyCode::Char('n') | KeyCode::Char('N') => {
-                    return Some(Box::new(Converting::new(self.file_name, None, true)));
-                }
-                _ => (),
+    fn open(&mut self, name: &str) {
+        if name == ".." {
+            if let Some(parent) = self.dir.parent() {
+                self.dir = parent.to_path_buf();
             }
+        } else {
+            self.dir.push(name);
         }
-        Some(self)
+        self.read_dir();
     }
 
-    fn draw(&mut self, _theme: &Theme, frame: &mut Frame) {
-        let popup = Block::bordered();
-        let areas = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
-            .split(popup_area(frame.area(), 70, 70));
-        let mut text_area = areas[0].clone();
-        text_area.y += text_area.height / 2;
-        frame.render_widget(Clear, areas[0]);
-        frame.render_widget(Clear, areas[1]);
-        frame.render_widget(popup, areas[0]);
-        frame.render_widget(
+    fn read_dir(&mut self) {
+        self.list.select(Some(0));
+        match list_dir(&self.dir, self.extensions) {
+            Ok(entries) => {
+                self.entries = vec![("..".to_string(), true)];
+                self.entries.extend(entries);
+                self.error.clear();
+            }
+            Err(e) => {
+                self.entries = vec![("..".to_string(), true)];
+                self.error = format!("{e}!");
+            }
+        }
+    }
+}
+
+fn list_dir(dir: &Path, extensions: &[&str]) -> std::io::Result<Vec<(String, bool)>> {
+    let mut entries = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let is_dir = entry.path().is_dir();
+        if name.starts_with('.') {
+            continue;
+        }
+        if is_dir || extensions.iter().any(|extension| name.ends_with(extension)) {
+            entries.push((name, is_dir));
+        }
+    }
+    entries.sort_by(|(a, a_is_dir), (b, b_is_dir)| b_is_dir.cmp(a_is_dir).then(a.cmp(b)));
+    Ok(entries)
+}
+
+fn complete_path(value: &str, extensions: &[&str]) -> Option<String> {
+    let (dir, partial) = match value.rfind(std::path::is_separator) {
+        Some(i) => (&value[..=i], &value[i + 1..]),
+        None => ("", value),
+    };
+    let matches = list_dir(
+        Path::new(if dir.is_empty() { "." } else { dir }),
+        extensions,
+    )
+    .ok()?
+    .into_iter()
+    .filter(|(name, _)| name.starts_with(partial))
+    .collect::<Vec<_>>();
+    let completed = match matches.as_slice() {
+        [] => return None,
+        [(name, true)] => format!("{dir}{name}{}", std::path::MAIN_SEPARATOR),
+        [(name, false)] => format!("{dir}{name}"),
+        [(first, _), rest @ ..] => {
+            let common = rest.iter().fold(first.len(), |common, (name, _)| {
+                first
+                    .char_indices()
+                    .zip(name.chars())
+                    .find(|((_, a), b)| a != b)
+                    .map_or(first.len().min(name.len()), |((i, _), _)| i)
+                    .min(common)
+            });
+            format!("{dir}{}", &first[..common])
+        }
+    };
+    (completed != value).then_some(completed)
+}
+
+fn set_value(state: &mut TextState<'static>, value: &str) {
+    *state = new_text_state(value).with_focus(state.focus_state());
+}
+
+impl PathPicker for FilePrompt {
+    fn pick_path(&mut self, path: &str, _app: &App) {
+        set_value(&mut self.file_name, path);
+        self.validate();
+    }
+}
+
+struct SettingsPrompt {
+    settings: Settings,
+    selected: usize,
+}
+
+impl Context for SettingsPrompt {
+    fn handle_event(mut self: Box<Self>, app: &mut App, event: Event) -> Option<Box<dyn Context>> {
+        if let Event::Key(key) = event
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Esc => return None,
+                KeyCode::Enter => {
+                    app.settings = self.settings;
+                    return None;
+                }
+                KeyCode::Char('j') | KeyCode::Char('J') | KeyCode::Down => {
+                    self.selected = (self.selected + 1) % Self::LABELS.len()
+                }
+                KeyCode::Char('k') | KeyCode::Char('K') | KeyCode::Up => {
+                    self.selected = (self.selected + Self::LABELS.len() - 1) % Self::LABELS.len()
+                }
+                KeyCode::Char(' ') | KeyCode::Right => self.change(true),
+                KeyCode::Left => self.change(false),
+                _ => (),
+            }
+        }
+        Some(self)
+    }
+
+    fn draw(&mut self, _theme: &Theme, frame: &mut Frame) {
+        let popup = Block::bordered().title("Options");
+        let areas = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+            .split(popup_area(frame.area(), 70, 70));
+        frame.render_widget(Clear, areas[0]);
+        frame.render_widget(popup, areas[0]);
+        frame.render_widget(Clear, areas[1]);
+        frame.render_widget(
+            Line::from("(J) Next (K) Prev (Left/Right) Change (Enter) Save").right_aligned(),
+            areas[1],
+        );
+
+        let areas = Layout::vertical(vec![Constraint::Length(1); Self::LABELS.len()])
+            .margin(2)
+            .split(areas[0]);
+        for (i, (label, value)) in Self::LABELS.iter().zip(self.values()).enumerate() {
+            let line = Line::from(format!("{label}: < {value} >"));
+            if i == self.selected {
+                frame.render_widget(line.reversed(), areas[i]);
+            } else {
+                frame.render_widget(line, areas[i]);
+            }
+        }
+    }
+}
+
+impl SettingsPrompt {
+    const LABELS: [&str; 9] = [
+        "Fist style",
+        "Pain colour",
+        "Pain strength",
+        "Damage ramp",
+        "Mugshot expressions",
+        "Mugshot shoulders",
+        "Mugshot backdrop",
+        "Render cache",
+        "Render jobs",
+    ];
+    const PAIN_COLORS: [(&str, [u8; 3]); 4] = [
+        ("Red", [255, 0, 0]),
+        ("Orange", [255, 128, 0]),
+        ("Purple", [160, 0, 255]),
+        ("Black", [0, 0, 0]),
+    ];
+
+    fn new(settings: &Settings) -> Self {
+        Self {
+            settings: settings.clone(),
+            selected: 0,
+        }
+    }
+
+    fn change(&mut self, forward: bool) {
+        match self.selected {
+            0 => {
+                self.settings.fist_style = match self.settings.fist_style {
+                    FistStyle::Single => FistStyle::Alternating,
+                    FistStyle::Alternating => FistStyle::Single,
+                }
+            }
+            1 => {
+                let count = Self::PAIN_COLORS.len();
+                let current = Self::PAIN_COLORS
+                    .iter()
+                    .position(|(_, color)| *color == self.settings.pain_color)
+                    .unwrap_or(0);
+                let next = if forward {
+                    (current + 1) % count
+                } else {
+                    (current + count - 1) % count
+                };
+                self.settings.pain_color = Self::PAIN_COLORS[next].1;
+            }
+            2 => {
+                self.settings.pain_strength = if forward {
+                    self.settings.pain_strength.saturating_add(15)
+                } else {
+                    self.settings.pain_strength.saturating_sub(15)
+                }
+            }
+            3 => {
+                use DamageRamp::*;
+                self.settings.damage_ramp = match (self.settings.damage_ramp, forward) {
+                    (Reciprocal, true) | (Flat, false) => Linear,
+                    (Linear, true) | (Reciprocal, false) => Flat,
+                    (Flat, true) | (Linear, false) => Reciprocal,
+                }
+            }
+            4 => self.settings.mugshot_expressions = !self.settings.mugshot_expressions,
+            5 => self.settings.mugshot_shoulders = !self.settings.mugshot_shoulders,
+            6 => {
+                use Backdrop::*;
+                self.settings.mugshot_backdrop = match (self.settings.mugshot_backdrop, forward) {
+                    (None, true) | (Gradient, false) => Solid,
+                    (Solid, true) | (None, false) => Gradient,
+                    (Gradient, true) | (Solid, false) => None,
+                }
+            }
+            7 => {
+                self.settings.cache_dir = match self.settings.cache_dir {
+                    Some(_) => None,
+                    None => Some(Settings::default_cache_dir()),
+                }
+            }
+            8 => {
+                self.settings.jobs = if forward {
+                    (self.settings.jobs + 1).min(64)
+                } else {
+                    (self.settings.jobs - 1).max(1)
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn values(&self) -> [String; 9] {
+        let pain_color = Self::PAIN_COLORS
+            .iter()
+            .find(|(_, color)| *color == self.settings.pain_color)
+            .map(|(name, _)| name.to_string())
+            .unwrap_or_else(|| {
+                let [r, g, b] = self.settings.pain_color;
+                format!("#{r:02X}{g:02X}{b:02X}")
+            });
+        [
+            match self.settings.fist_style {
+                FistStyle::Single => "Single".into(),
+                FistStyle::Alternating => "Both arms".into(),
+            },
+            pain_color,
+            self.settings.pain_strength.to_string(),
+            match self.settings.damage_ramp {
+                DamageRamp::Reciprocal => "Steep".into(),
+                DamageRamp::Linear => "Linear".into(),
+                DamageRamp::Flat => "None".into(),
+            },
+            on_off(self.settings.mugshot_expressions),
+            on_off(self.settings.mugshot_shoulders),
+            match self.settings.mugshot_backdrop {
+                Backdrop::None => "None".into(),
+                Backdrop::Solid => "Solid".into(),
+                Backdrop::Gradient => "Gradient".into(),
+            },
+            on_off(self.settings.cache_dir.is_some()),
+            self.settings.jobs.to_string(),
+        ]
+    }
+}
+
+struct ConvertPrompt {
+    file_name: TextState<'static>,
+    renders_dir: TextState<'static>,
+    error: String,
+    renders_error: String,
+}
+
+impl Context for ConvertPrompt {
+    fn handle_event(mut self: Box<Self>, app: &mut App, event: Event) -> Option<Box<dyn Context>> {
+        if let Event::Key(key) = event {
+            match key.code {
+                KeyCode::Esc => return None,
+                KeyCode::Tab | KeyCode::BackTab => {
+                    let (field, extensions): (_, &[&str]) = if self.file_name.is_focused() {
+                        (&mut self.file_name, &[".wad"])
+                    } else {
+                        (&mut self.renders_dir, &[])
+                    };
+                    if key.code == KeyCode::Tab
+                        && let Some(path) = complete_path(field.value(), extensions)
+                    {
+                        set_value(field, &path);
+                        self.validate();
+                    } else if self.file_name.is_focused() {
+                        self.file_name.blur();
+                        self.renders_dir.focus();
+                    } else {
+                        self.renders_dir.blur();
+                        self.file_name.focus();
+                    }
+                }
+                KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    let file_name = self.file_name.value().to_string();
+                    return Some(Box::new(FileBrowser::new(self, &file_name, &[".wad"])));
+                }
+                KeyCode::Enter => {
+                    if self.file_name.status().is_done() && self.renders_error.is_empty() {
+                        app.settings.kept_renders_dir = Some(self.renders_dir.value())
+                            .filter(|dir| !dir.is_empty())
+                            .map(PathBuf::from);
+                        return Some(Box::new(GenerationPrompt::new(
+                            self.file_name.value().to_string(),
+                        )));
+                    }
+                }
+                _ => {
+                    if self.file_name.is_focused() {
+                        self.file_name.handle_key_event(key);
+                    } else {
+                        self.renders_dir.handle_key_event(key);
+                    }
+                    self.validate();
+                }
+            }
+        }
+        Some(self)
+    }
+
+    fn draw(&mut self, _theme: &Theme, frame: &mut Frame) {
+        let popup = Block::bordered();
+        let areas = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+            .split(popup_area(frame.area(), 70, 70));
+        frame.render_widget(Clear, areas[0]);
+        frame.render_widget(popup, areas[0]);
+        frame.render_widget(Clear, areas[1]);
+        frame.render_widget(
+            Line::from("(Tab) Next (Ctrl+O) Browse (Enter) Submit").right_aligned(),
+            areas[1],
+        );
+
+        let areas = Layout::vertical(vec![Constraint::Length(1); 4])
+            .margin(2)
+            .split(areas[0]);
+        TextPrompt::from("File name").draw(frame, areas[0], &mut self.file_name);
+        frame.render_widget(Line::from(self.error.clone()), areas[1]);
+        TextPrompt::from("Keep renders in (optional)").draw(frame, areas[2], &mut self.renders_dir);
+        frame.render_widget(Line::from(self.renders_error.clone()), areas[3]);
+    }
+}
+
+impl ConvertPrompt {
+    fn new(settings: &Settings) -> Self {
+        let renders_dir = settings
+            .kept_renders_dir
+            .as_ref()
+            .map(|dir| new_text_state(&dir.to_string_lossy()))
+            .unwrap_or_default();
+        Self {
+            file_name: TextState::default().with_focus(FocusState::Focused),
+            renders_dir,
+            error: String::new(),
+            renders_error: String::new(),
+        }
+    }
+
+    fn validate(&mut self) {
+        *self.file_name.status_mut() = Status::Aborted;
+        if !self.file_name.value().ends_with(".wad") {
+            self.error = "Must be a wad file!".into();
+        } else {
+            *self.file_name.status_mut() = Status::Done;
+            self.error.clear();
+        }
+
+        if Path::new(self.renders_dir.value()).is_file() {
+            self.renders_error = "Must be a folder!".into();
+        } else {
+            self.renders_error.clear();
+        }
+    }
+}
+
+impl PathPicker for ConvertPrompt {
+    fn pick_path(&mut self, path: &str, _app: &App) {
+        set_value(&mut self.file_name, path);
+        self.renders_dir.blur();
+        self.file_name.focus();
+        self.validate();
+    }
+}
+
+struct PreviewPrompt {
+    index: usize,
+    file_name: TextState<'static>,
+    error: String,
+}
+
+impl Context for PreviewPrompt {
+    fn handle_event(mut self: Box<Self>, app: &mut App, event: Event) -> Option<Box<dyn Context>> {
+        if let Event::Key(key) = event {
+            match key.code {
+                KeyCode::Esc => return None,
+                KeyCode::Enter => {
+                    if self.file_name.status().is_done() {
+                        return Some(Box::new(Previewing::new(
+                            app,
+                            self.index,
+                            self.file_name.value().to_string(),
+                        )));
+                    }
+                }
+                _ => {
+                    self.file_name.handle_key_event(key);
+                    self.validate();
+                }
+            }
+        }
+        Some(self)
+    }
+
+    fn draw(&mut self, _theme: &Theme, frame: &mut Frame) {
+        let popup = Block::bordered();
+        let areas = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+            .split(popup_area(frame.area(), 70, 70));
+        frame.render_widget(Clear, areas[0]);
+        frame.render_widget(popup, areas[0]);
+        frame.render_widget(Clear, areas[1]);
+        frame.render_widget(Line::from("(Enter) Preview").right_aligned(), areas[1]);
+
+        let areas = Layout::vertical(vec![Constraint::Length(1); 2])
+            .margin(2)
+            .split(areas[0]);
+        TextPrompt::from("File name").draw(frame, areas[0], &mut self.file_name);
+        frame.render_widget(Line::from(self.error.clone()).red(), areas[1]);
+    }
+}
+
+impl PreviewPrompt {
+    fn new(index: usize) -> Self {
+        Self {
+            index,
+            file_name: TextState::default().with_focus(FocusState::Focused),
+            error: String::new(),
+        }
+    }
+
+    fn validate(&mut self) {
+        *self.file_name.status_mut() = Status::Aborted;
+        if !self.file_name.value().ends_with(".png") {
+            self.error = "Must be a png file!".into();
+        } else {
+            *self.file_name.status_mut() = Status::Done;
+            self.error.clear();
+        }
+    }
+}
+
+type Job = dyn FnOnce(&(dyn Fn(Progress) + Sync), &AtomicBool) -> Result<(), ConvertError> + Send;
+
+/// A conversion or preview rendering on a worker thread, reporting its progress back.
+struct Task {
+    worker: Option<JoinHandle<Result<(), ConvertError>>>,
+    receiver: Receiver<Progress>,
+    progress: Option<Progress>,
+    warnings: Vec<String>,
+    cancelled: Arc<AtomicBool>,
+}
+
+enum Outcome {
+    Running,
+    Done,
+    Cancelled,
+    Failed(anyhow::Error),
+}
+
+impl Task {
+    fn spawn(job: Box<Job>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let worker = {
+            let cancelled = cancelled.clone();
+            std::thread::spawn(move || {
+                job(
+                    &|progress| {
+                        let _ = sender.send(progress);
+                    },
+                    &cancelled,
+                )
+            })
+        };
+        Self {
+            worker: Some(worker),
+            receiver,
+            progress: None,
+            warnings: vec![],
+            cancelled,
+        }
+    }
+
+    /// Cancels on Esc and collects the progress reported so far, then checks whether the worker
+    /// is done.
+    fn update(&mut self, event: &Event) -> Outcome {
+        if let Event::Key(key) = event
+            && key.kind == KeyEventKind::Press
+            && key.code == KeyCode::Esc
+        {
+            self.cancelled.store(true, Ordering::SeqCst);
+        }
+        for progress in self.receiver.try_iter() {
+            if progress.warning {
+                self.warnings.push(progress.message);
+            } else {
+                self.progress = Some(progress);
+            }
+        }
+        let Some(worker) = self.worker.take_if(|worker| worker.is_finished()) else {
+            return Outcome::Running;
+        };
+
+        let discarded = discard_events();
+        match worker.join() {
+            Ok(Err(ConvertError::Cancelled)) => match discarded {
+                Ok(()) => Outcome::Cancelled,
+                Err(e) => Outcome::Failed(e),
+            },
+            Ok(Err(e)) => Outcome::Failed(anyhow::Error::new(e)),
+            Err(_) => Outcome::Failed(anyhow::anyhow!("the worker thread panicked")),
+            Ok(Ok(())) => match discarded {
+                Ok(()) => Outcome::Done,
+                Err(e) => Outcome::Failed(e),
+            },
+        }
+    }
+
+    fn draw(&self, title: &str, frame: &mut Frame) {
+        let popup = Block::bordered();
+        let areas = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+            .split(popup_area(frame.area(), 70, 70));
+        frame.render_widget(Clear, areas[0]);
+        frame.render_widget(popup, areas[0]);
+        frame.render_widget(Clear, areas[1]);
+        frame.render_widget(Line::from("(Esc) Cancel").right_aligned(), areas[1]);
+
+        let area = areas[0].inner(Margin::new(2, 1));
+        let areas = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(self.warnings.len() as u16),
+        ])
+        .flex(Flex::Center)
+        .split(area);
+        let warnings = self
+            .warnings
+            .iter()
+            .map(|warning| Line::from(warning.as_str()).yellow())
+            .collect::<Vec<_>>();
+        frame.render_widget(Paragraph::new(warnings).centered(), areas[3]);
+        let Some(progress) = &self.progress else {
+            frame.render_widget(Line::from(title).centered(), areas[0]);
+            return;
+        };
+        let message = if self.cancelled.load(Ordering::SeqCst) {
+            "Cancelling...".into()
+        } else {
+            progress.message.clone()
+        };
+        frame.render_widget(Line::from(message).centered(), areas[0]);
+        frame.render_widget(
+            Gauge::default()
+                .ratio(progress.completed as f64 / progress.total.max(1) as f64)
+                .label(format!("{}/{}", progress.completed, progress.total)),
+            areas[1],
+        );
+    }
+}
+
+struct Previewing {
+    file_name: String,
+    task: Task,
+}
+
+impl Previewing {
+    fn new(app: &App, index: usize, file_name: String) -> Self {
+        let (item, settings, sheet) = (
+            app.items[index].clone(),
+            app.settings.clone(),
+            file_name.clone(),
+        );
+        let task = Task::spawn(Box::new(move |progress, cancelled| {
+            crate::converting::preview(&item, &settings, Path::new(&sheet), progress, cancelled)
+        }));
+        Self { file_name, task }
+    }
+}
+
+impl Context for Previewing {
+    fn handle_event(mut self: Box<Self>, _app: &mut App, event: Event) -> Option<Box<dyn Context>> {
+        match self.task.update(&event) {
+            Outcome::Running => Some(self),
+            Outcome::Cancelled => None,
+            Outcome::Failed(e) => Some(Box::new(ErrorPopup::new(e.context("preview failed")))),
+            Outcome::Done => Some(Box::new(Notice::new(format!(
+                "'{}' created successfully!",
+                self.file_name
+            )))),
+        }
+    }
+
+    fn draw(&mut self, _theme: &Theme, frame: &mut Frame) {
+        self.task.draw("Rendering preview...", frame);
+    }
+}
+
+struct Notice {
+    message: String,
+}
+
+impl Notice {
+    fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl Context for Notice {
+    fn handle_event(self: Box<Self>, _app: &mut App, event: Event) -> Option<Box<dyn Context>> {
+        if let Event::Key(_) = event {
+            return None;
+        }
+        Some(self)
+    }
+
+    fn draw(&mut self, _theme: &Theme, frame: &mut Frame) {
+        let popup = Block::bordered();
+        let areas = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+            .split(popup_area(frame.area(), 70, 70));
+        let mut text_area = areas[0];
+        text_area.y += text_area.height / 2;
+        text_area.height -= text_area.height / 2;
+        frame.render_widget(Clear, areas[0]);
+        frame.render_widget(Clear, areas[1]);
+        frame.render_widget(popup, areas[0]);
+        frame.render_widget(
+            Paragraph::new(self.message.clone())
+                .centered()
+                .wrap(ratatui::widgets::Wrap { trim: true }),
+            text_area.inner(Margin {
+                vertical: 0,
+                horizontal: 1,
+            }),
+        );
+        frame.render_widget(Line::from("(Any) Back").right_aligned(), areas[1]);
+    }
+}
+
+struct ErrorPopup {
+    error: anyhow::Error,
+}
+
+impl ErrorPopup {
+    fn new(error: anyhow::Error) -> Self {
+        Self { error }
+    }
+}
+
+impl Context for ErrorPopup {
+    fn handle_event(self: Box<Self>, _app: &mut App, event: Event) -> Option<Box<dyn Context>> {
+        if let Event::Key(_) = event {
+            return None;
+        }
+        Some(self)
+    }
+
+    fn draw(&mut self, _theme: &Theme, frame: &mut Frame) {
+        let popup = Block::bordered().title("Error").red();
+        let areas = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+            .split(popup_area(frame.area(), 70, 70));
+        frame.render_widget(Clear, areas[0]);
+        frame.render_widget(Clear, areas[1]);
+        frame.render_widget(popup, areas[0]);
+
+        let lines = self
+            .error
+            .chain()
+            .enumerate()
+            .map(|(i, cause)| {
+                if i == 0 {
+                    Line::from(cause.to_string()).bold()
+                } else {
+                    Line::from(format!("caused by: {cause}"))
+                }
+            })
+            .collect::<Vec<_>>();
+        frame.render_widget(
+            Paragraph::new(lines).wrap(ratatui::widgets::Wrap { trim: true }),
+            areas[0].inner(Margin {
+                vertical: 1,
+                horizontal: 2,
+            }),
+        );
+        frame.render_widget(Line::from("(Any) Back").right_aligned(), areas[1]);
+    }
+}
+
+struct ImportReport {
+    report: Vec<String>,
+}
+
+impl Context for ImportReport {
+    fn handle_event(self: Box<Self>, _app: &mut App, event: Event) -> Option<Box<dyn Context>> {
+        if let Event::Key(_) = event {
+            return None;
+        }
+        Some(self)
+    }
+
+    fn draw(&mut self, _theme: &Theme, frame: &mut Frame) {
+        let popup = Block::bordered().title("Import");
+        let areas = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+            .split(popup_area(frame.area(), 70, 70));
+        frame.render_widget(Clear, areas[0]);
+        frame.render_widget(Clear, areas[1]);
+        frame.render_widget(popup, areas[0]);
+
+        let lines = self
+            .report
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                if i == 0 {
+                    Line::from(line.clone()).bold()
+                } else {
+                    Line::from(line.clone())
+                }
+            })
+            .collect::<Vec<_>>();
+        frame.render_widget(
+            Paragraph::new(lines).wrap(ratatui::widgets::Wrap { trim: true }),
+            areas[0].inner(Margin {
+                vertical: 1,
+                horizontal: 2,
+            }),
+        );
+        frame.render_widget(Line::from("(Any) Back").right_aligned(), areas[1]);
+    }
+}
+
+struct GenerationPrompt {
+    file_name: String,
+}
+
+impl Context for GenerationPrompt {
+    fn handle_event(self: Box<Self>, app: &mut App, event: Event) -> Option<Box<dyn Context>> {
+        if let Event::Key(key) = event {
+            match key.code {
+                KeyCode::Esc => return None,
+                KeyCode::Char('d') | KeyCode::Char('D') => {
+                    return Some(Box::new(Converting::new(app, self.file_name, None, false)));
+                }
+                KeyCode::Char('s') | KeyCode::Char('S') => {
+                    if let Some(acc) = get_acc() {
+                        return Some(Box::new(FistConfirm::new(self.file_name, acc)));
+                    } else {
+                        return Some(Box::new(Converting::new(app, self.file_name, None, true)));
+                    }
+                }
+                _ => (),
+            }
+        }
+        Some(self)
+    }
+
+    fn draw(&mut self, _theme: &Theme, frame: &mut Frame) {
+        let popup = Block::bordered();
+        let areas = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+            .split(popup_area(frame.area(), 70, 70));
+        let mut text_area = areas[0];
+        text_area.y += text_area.height / 2;
+        frame.render_widget(Clear, areas[0]);
+        frame.render_widget(Clear, areas[1]);
+        frame.render_widget(popup, areas[0]);
+        frame.render_widget(
+            Paragraph::new("Would you like to generate using DECORATE or S_SKIN?").centered(),
+            text_area,
+        );
+        frame.render_widget(
+            Line::from("(D) DECORATE (S) S_SKIN").right_aligned(),
+            areas[1],
+        );
+    }
+}
+
+impl GenerationPrompt {
+    fn new(file_name: String) -> Self {
+        Self { file_name }
+    }
+}
+
+struct FistConfirm {
+    file_name: String,
+    acc: PathBuf,
+}
+
+impl Context for FistConfirm {
+    fn handle_event(self: Box<Self>, app: &mut App, event: Event) -> Option<Box<dyn Context>> {
+        if let Event::Key(key) = event {
+            match key.code {
+                KeyCode::Esc => return None,
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    return Some(Box::new(Converting::new(
+                        app,
+                        self.file_name,
+                        Some(self.acc),
+                        true,
+                    )));
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') => {
+                    return Some(Box::new(Converting::new(app, self.file_name, None, true)));
+                }
+                _ => (),
+            }
+        }
+        Some(self)
+    }
+
+    fn draw(&mut self, _theme: &Theme, frame: &mut Frame) {
+        let popup = Block::bordered();
+        let areas = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+            .split(popup_area(frame.area(), 70, 70));
+        let mut text_area = areas[0];
+        text_area.y += text_area.height / 2;
+        frame.render_widget(Clear, areas[0]);
+        frame.render_widget(Clear, areas[1]);
+        frame.render_widget(popup, areas[0]);
+        frame.render_widget(
             Paragraph::new("ACC detected: would you like to generate a separate fist wad?")
                 .centered(),
             text_area,
@@ -664,50 +1976,55 @@ impl FistConfirm {
 struct Converting {
     file_name: String,
     acc: Option<PathBuf>,
-    as_skins: bool,
+    task: Task,
 }
 
 impl Converting {
-    fn new(file_name: String, acc: Option<PathBuf>, as_skins: bool) -> Self {
+    fn new(app: &App, file_name: String, acc: Option<PathBuf>, as_skins: bool) -> Self {
+        let format = if !as_skins {
+            Format::DecorateWad
+        } else if acc.is_some() {
+            Format::S_SkinAndFistWads
+        } else {
+            Format::S_SkinWad
+        };
+        let (items, settings, produced_file) =
+            (app.items.clone(), app.settings.clone(), file_name.clone());
+        let task = Task::spawn(Box::new(move |progress, cancelled| {
+            crate::converting::convert(
+                &items,
+                format,
+                &settings,
+                Path::new(&produced_file),
+                progress,
+                cancelled,
+            )
+        }));
         Self {
             file_name,
             acc,
-            as_skins,
+            task,
         }
     }
 }
 
 impl Context for Converting {
-    fn handle_event(self: Box<Self>, app: &mut App, _event: Event) -> Option<Box<dyn Context>> {
-        let _gag = gag::Gag::stdout().unwrap();
-        let format = if !self.as_skins {
-            Format::DecorateWad
-        } else if self.acc.is_some() {
-            Format::S_SkinAndFistWads
-        } else {
-            Format::S_SkinWad
-        };
-        crate::converting::convert(&app.items, format, Path::new(&self.file_name)).unwrap();
-        while poll(Duration::from_millis(0)).unwrap() {
-            event::read().unwrap();
+    fn handle_event(mut self: Box<Self>, _app: &mut App, event: Event) -> Option<Box<dyn Context>> {
+        match self.task.update(&event) {
+            Outcome::Running => Some(self),
+            Outcome::Cancelled => None,
+            Outcome::Failed(e) => Some(Box::new(ErrorPopup::new(
+                e.context(format!("converting '{}' failed", self.file_name)),
+            ))),
+            Outcome::Done if self.acc.is_some() => {
+                Some(Box::new(Success::new_with_fists(self.file_name)))
+            }
+            Outcome::Done => Some(Box::new(Success::new(self.file_name))),
         }
-        let success = if self.acc.is_some() {
-            Success::new_with_fists(self.file_name)
-        } else {
-            Success::new(self.file_name)
-        };
-        return Some(Box::new(success));
     }
 
     fn draw(&mut self, _theme: &Theme, frame: &mut Frame) {
-        let popup = Block::bordered();
-
-        let mut area = popup_area(frame.area(), 70, 70);
-        frame.render_widget(Clear, area);
-        frame.render_widget(popup, area);
-
-        area.y += area.height / 2;
-        frame.render_widget(Line::from("Converting...").centered(), area);
+        self.task.draw("Converting...", frame);
     }
 }
 
@@ -776,16 +2093,17 @@ struct App {
     items: Vec<SkinData>,
     subcontext: Option<Box<dyn Context>>,
     theme: Theme,
+    settings: Settings,
 }
 
 impl App {
-    fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
+    fn run(mut self, mut terminal: Tui) -> Result<()> {
         let mut context = MainContext::default();
 
         while !self.quit {
             if let Some(mut subcontext) = self.subcontext.take() {
                 terminal.draw(|frame| {
-                    context.draw(&self.theme, &self.items, frame);
+                    context.draw(&self.theme, &self.items, &self.settings, frame);
                     subcontext.draw(&self.theme, frame);
                 })?;
                 if poll(Duration::from_millis(100))? {
@@ -794,14 +2112,29 @@ impl App {
                     self.subcontext = subcontext.handle_event(&mut self, Event::FocusGained);
                 }
             } else {
-                terminal.draw(|frame| context.draw(&self.theme, &self.items, frame))?;
-                context.handle_event(&mut self, event::read()?);
+                terminal
+                    .draw(|frame| context.draw(&self.theme, &self.items, &self.settings, frame))?;
+                if poll(Duration::from_millis(100))? {
+                    context.handle_event(&mut self, event::read()?);
+                }
             }
         }
         Ok(())
     }
 }
 
+fn on_off(value: bool) -> String {
+    if value { "On".into() } else { "Off".into() }
+}
+
+/// Drops the keys pressed while a blocking task ran, so they don't act on the next popup.
+fn discard_events() -> anyhow::Result<()> {
+    while poll(Duration::ZERO).context("reading the terminal failed")? {
+        event::read().context("reading the terminal failed")?;
+    }
+    Ok(())
+}
+
 fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
     let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
@@ -810,22 +2143,231 @@ fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     area
 }
 
-#[derive(Default)]
+#[derive(Default, PartialEq)]
 enum ItemField {
     #[default]
     Name,
     Path,
     Sprite,
+    Sprites,
+}
+
+fn read_skins(path: &Path) -> anyhow::Result<Vec<SkinData>> {
+    let mut reader = Reader::from_reader(BufReader::new(File::open(path)?));
+    let mut skins = vec![];
+    for result in reader.deserialize() {
+        skins.push(result?);
+    }
+    Ok(skins)
+}
+
+fn settings_path(csv: &Path) -> PathBuf {
+    csv.with_extension("settings.csv")
+}
+
+fn read_settings(csv: &Path) -> anyhow::Result<Option<Settings>> {
+    let path = settings_path(csv);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut reader = Reader::from_reader(BufReader::new(File::open(&path)?));
+    let settings = reader
+        .deserialize()
+        .next()
+        .transpose()
+        .with_context(|| path.display().to_string())?;
+    Ok(settings)
+}
+
+fn write_settings(csv: &Path, settings: &Settings) -> anyhow::Result<()> {
+    let path = settings_path(csv);
+    let mut writer = csv::Writer::from_path(&path)?;
+    writer
+        .serialize(settings)
+        .with_context(|| path.display().to_string())?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn import_skins(dir: &Path, existing: &[SkinData]) -> anyhow::Result<(Vec<SkinData>, Vec<String>)> {
+    let mut paths = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("png"))
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut skins: Vec<SkinData> = vec![];
+    let mut notes = vec![];
+    for path in paths {
+        let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+        let path = path.to_string_lossy().to_string();
+        if existing.iter().any(|skin| skin.path == path) {
+            notes.push(format!("Skipped {file_name}: already in the list"));
+            continue;
+        }
+        match validate_skin(Path::new(&path)) {
+            Err(e) => notes.push(format!("Skipped {file_name}: {e}")),
+            Ok(warnings) => {
+                for warning in warnings {
+                    notes.push(format!("Warning for {file_name}: {warning}"));
+                }
+                let name = Path::new(&file_name)
+                    .file_stem()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string();
+                let sprite_prefix = suggest_sprite_prefix(&name, existing.iter().chain(&skins));
+                skins.push(SkinData {
+                    name,
+                    path,
+                    sprite_prefix,
+                    sprites: String::new(),
+                });
+            }
+        }
+    }
+    let mut report = vec![format!("Imported {} skins", skins.len())];
+    report.extend(notes);
+    Ok((skins, report))
+}
+
+#[derive(clap::Parser)]
+#[command(version, about = "Convert Minecraft skins into Doom player sprites")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    #[command(
+        about = "Convert the skins listed in a csv file, with the options saved beside it, without opening the interface"
+    )]
+    Convert {
+        csv: PathBuf,
+        output: PathBuf,
+        #[arg(long, value_enum, default_value_t = CliFormat::Decorate)]
+        format: CliFormat,
+        #[arg(long)]
+        jobs: Option<usize>,
+    },
+}
+
+#[derive(clap::ValueEnum, Clone)]
+enum CliFormat {
+    Decorate,
+    SSkin,
+    SSkinAndFists,
+}
+
+fn run_command(command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Convert {
+            csv,
+            output,
+            format,
+            jobs,
+        } => {
+            let format = match format {
+                CliFormat::Decorate => Format::DecorateWad,
+                CliFormat::SSkin => Format::S_SkinWad,
+                CliFormat::SSkinAndFists => {
+                    if get_acc().is_none() {
+                        anyhow::bail!("acc is required to build the fist wad");
+                    }
+                    Format::S_SkinAndFistWads
+                }
+            };
+            let mut settings = read_settings(&csv)?.unwrap_or_default();
+            if let Some(jobs) = jobs {
+                settings.jobs = jobs.max(1);
+            }
+            let skins = read_skins(&csv)?;
+            let _gag = gag::Gag::stdout()?;
+            let cancelled = AtomicBool::new(false);
+            convert(
+                &skins,
+                format,
+                &settings,
+                &output,
+                &|progress| {
+                    if progress.warning {
+                        eprintln!("\rwarning: {}", progress.message);
+                        return;
+                    }
+                    let filled = progress.completed * 30 / progress.total.max(1);
+                    eprint!(
+                        "\r[{}{}] {}/{} {:<48.48}",
+                        "#".repeat(filled),
+                        "-".repeat(30 - filled),
+                        progress.completed,
+                        progress.total,
+                        progress.message
+                    );
+                },
+                &cancelled,
+            )?;
+            eprintln!();
+            Ok(())
+        }
+    }
 }
 
 fn main() {
+    let cli = <Cli as clap::Parser>::parse_from(wild::args());
+    if let Some(command) = cli.command {
+        if let Err(e) = run_command(command) {
+            eprintln!("\n{e:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     color_eyre::install().unwrap();
-    let terminal = ratatui::init();
-    let result = App::default().run(terminal);
-    ratatui::restore();
+    let result = init_terminal().and_then(|terminal| {
+        // putpng and some GL drivers print to stdout, which would scribble over the interface,
+        // so it stays gagged while the terminal draws through its own copy of the descriptor.
+        let _gag = gag::Gag::stdout().ok();
+        App::default().run(terminal)
+    });
+    restore_terminal();
     let _ = result.inspect_err(|e| eprintln!("{}", e));
 }
 
+type Tui = Terminal<CrosstermBackend<File>>;
+
+fn init_terminal() -> Result<Tui> {
+    #[cfg(unix)]
+    let tty = std::os::fd::AsFd::as_fd(&std::io::stdout()).try_clone_to_owned()?;
+    #[cfg(windows)]
+    let tty = std::os::windows::io::AsHandle::as_handle(&std::io::stdout()).try_clone_to_owned()?;
+    let tty = File::from(tty);
+
+    let panic_tty = tty.try_clone()?;
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = crossterm::terminal::disable_raw_mode();
+        let _ = crossterm::execute!(&panic_tty, crossterm::terminal::LeaveAlternateScreen);
+        hook(info);
+    }));
+
+    crossterm::terminal::enable_raw_mode()?;
+    let mut backend = CrosstermBackend::new(tty);
+    crossterm::execute!(backend, crossterm::terminal::EnterAlternateScreen)?;
+    Ok(Terminal::new(backend)?)
+}
+
+fn restore_terminal() {
+    let _ = crossterm::terminal::disable_raw_mode();
+    let _ = crossterm::execute!(std::io::stdout(), crossterm::terminal::LeaveAlternateScreen);
+}
+
 fn validate_sprite(sprite: &str) -> bool {
     for c in sprite.chars() {
         if !c.is_ascii() || !c.is_alphabetic() && c != '[' && c != ']' && c != '\\' {
@@ -834,3 +2376,31 @@ fn validate_sprite(sprite: &str) -> bool {
     }
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::MAIN_SEPARATOR;
+    use tempfile::tempdir;
+
+    #[test]
+    fn complete_path_fills_in_matching_entries() {
+        let temp = tempdir().unwrap();
+        let dir = format!("{}{MAIN_SEPARATOR}", temp.path().display());
+        std::fs::create_dir(temp.path().join("skins")).unwrap();
+        std::fs::create_dir(temp.path().join(".hidden")).unwrap();
+        for file in ["steve.png", "stevie.png", "notes.txt", ".secret.png"] {
+            std::fs::write(temp.path().join(file), []).unwrap();
+        }
+        let complete = |value: &str| complete_path(&format!("{dir}{value}"), &[".png"]);
+
+        assert_eq!(complete("ste"), Some(format!("{dir}stev")));
+        assert_eq!(complete("steve."), Some(format!("{dir}steve.png")));
+        assert_eq!(complete("sk"), Some(format!("{dir}skins{MAIN_SEPARATOR}")));
+        // Already as complete as it gets.
+        assert_eq!(complete("stev"), None);
+        // Hidden entries and other extensions are never offered.
+        assert_eq!(complete("no"), None);
+        assert_eq!(complete("."), None);
+    }
+}
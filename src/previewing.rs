@@ -0,0 +1,188 @@
+use anyhow::Context;
+use image::{Rgba, RgbaImage};
+use std::path::Path;
+
+const COLUMNS: u32 = 8;
+const PADDING: u32 = 4;
+const LABEL_HEIGHT: u32 = 8;
+const BACKGROUND: Rgba<u8> = Rgba([32, 32, 32, 255]);
+const LABEL: Rgba<u8> = Rgba([200, 200, 200, 255]);
+const TITLE: Rgba<u8> = Rgba([110, 231, 183, 255]);
+
+struct Group {
+    title: String,
+    frames: Vec<(String, RgbaImage)>,
+    bounds: (u32, u32, u32, u32),
+}
+
+impl Group {
+    fn load(rendered_dir: &Path, subdir: &str, index: usize) -> anyhow::Result<Option<Self>> {
+        let dir = rendered_dir.join(format!("{subdir}{index}"));
+        if !dir.exists() {
+            return Ok(None);
+        }
+        let mut paths = std::fs::read_dir(&dir)
+            .with_context(|| format!("subdirectory {subdir}{index}"))?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+
+        let mut frames = vec![];
+        let mut bounds: Option<(u32, u32, u32, u32)> = None;
+        for path in paths {
+            let image = image::open(&path)
+                .with_context(|| path.display().to_string())?
+                .to_rgba8();
+            for (x, y, pixel) in image.enumerate_pixels() {
+                if pixel[3] > 0 {
+                    bounds = Some(match bounds {
+                        Some((left, top, right, bottom)) => {
+                            (left.min(x), top.min(y), right.max(x), bottom.max(y))
+                        }
+                        None => (x, y, x, y),
+                    });
+                }
+            }
+            let name = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .replace('^', "\\");
+            frames.push((name, image));
+        }
+
+        Ok(bounds.map(|bounds| Self {
+            title: subdir.to_uppercase(),
+            frames,
+            bounds,
+        }))
+    }
+
+    fn cell_size(&self) -> (u32, u32) {
+        let (left, top, right, bottom) = self.bounds;
+        let label_width = self
+            .frames
+            .iter()
+            .map(|(name, _)| text_width(name))
+            .max()
+            .unwrap_or(0);
+        (
+            (right - left + 1).max(label_width) + PADDING,
+            bottom - top + 1 + LABEL_HEIGHT + PADDING,
+        )
+    }
+
+    fn size(&self) -> (u32, u32) {
+        let (cell_width, cell_height) = self.cell_size();
+        let rows = (self.frames.len() as u32).div_ceil(COLUMNS);
+        (COLUMNS * cell_width, LABEL_HEIGHT + rows * cell_height)
+    }
+
+    fn draw(&self, sheet: &mut RgbaImage, x: u32, y: u32) {
+        let (left, top, right, bottom) = self.bounds;
+        let (cell_width, cell_height) = self.cell_size();
+        draw_text(sheet, x, y, &self.title, TITLE);
+
+        for (i, (name, image)) in self.frames.iter().enumerate() {
+            let cell_x = x + (i as u32 % COLUMNS) * cell_width;
+            let cell_y = y + LABEL_HEIGHT + (i as u32 / COLUMNS) * cell_height;
+            let frame =
+                image::imageops::crop_imm(image, left, top, right - left + 1, bottom - top + 1)
+                    .to_image();
+            image::imageops::overlay(sheet, &frame, cell_x as i64, cell_y as i64);
+            draw_text(sheet, cell_x, cell_y + frame.height() + 1, name, LABEL);
+        }
+    }
+}
+
+pub fn produce_contact_sheet(
+    rendered_dir: &Path,
+    index: usize,
+    sheet: &Path,
+) -> anyhow::Result<()> {
+    let mut groups = vec![];
+    for subdir in ["sprites", "crouch-sprites", "fist", "mugshot"] {
+        if let Some(group) = Group::load(rendered_dir, subdir, index)? {
+            groups.push(group);
+        }
+    }
+
+    let width = groups.iter().map(|g| g.size().0).max().unwrap_or(1) + PADDING;
+    let height = groups.iter().map(|g| g.size().1 + PADDING).sum::<u32>() + PADDING;
+    let mut image = RgbaImage::from_pixel(width, height, BACKGROUND);
+    let mut y = PADDING;
+    for group in &groups {
+        group.draw(&mut image, PADDING, y);
+        y += group.size().1 + PADDING;
+    }
+
+    image
+        .save(sheet)
+        .with_context(|| sheet.display().to_string())?;
+    Ok(())
+}
+
+fn text_width(text: &str) -> u32 {
+    text.len() as u32 * 4
+}
+
+fn draw_text(image: &mut RgbaImage, x: u32, y: u32, text: &str, color: Rgba<u8>) {
+    for (i, c) in text.chars().enumerate() {
+        for (row, bits) in glyph(c).into_iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) != 0 {
+                    let (px, py) = (x + i as u32 * 4 + column, y + row as u32);
+                    if px < image.width() && py < image.height() {
+                        image.put_pixel(px, py, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        '[' => [0b110, 0b100, 0b100, 0b100, 0b110],
+        ']' => [0b011, 0b001, 0b001, 0b001, 0b011],
+        '\\' => [0b100, 0b100, 0b010, 0b001, 0b001],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        _ => [0; 5],
+    }
+}
@@ -1,46 +1,19 @@
-use crate::converting::{SpritePrefix, get_acc};
+use crate::converting::{ConvertError, FistStyle, Settings, SkinRef, SpriteNames, get_acc};
 use anyhow::Context;
 use putpng::crc::Crc32;
 use std::path::Path;
-use tinywad::{
-    lump::{LumpAdd, LumpAddKind},
-    models::operation::WadOp,
-    wad::{Wad, WadKind},
-};
 
 trait Archive {
     fn new_archive() -> Self;
 
-    fn push_lump(&mut self, buffer: &Vec<u8>, name: &str) -> anyhow::Result<()>;
+    fn push_lump(&mut self, buffer: &[u8], name: &str);
 
-    fn grab_from(
-        &mut self,
-        rendered_dir: &Path,
-        subdir: &str,
-        index: usize,
-        x: &str,
-        y: &str,
-        crc: &Crc32,
-    ) -> anyhow::Result<()> {
-        let mut paths = std::fs::read_dir(rendered_dir.join(&format!("{subdir}{index}")))
-            .map(|d| d.map(|p| p.unwrap().path().to_str().unwrap().to_string()))
-            .with_context(|| format!("subdirectory {subdir}{index}"))?
-            .collect::<Vec<_>>();
-        paths.sort();
-        putpng::grab::grab_all(
-            paths.iter().map(|s| s.clone()),
-            &crc,
-            x.to_string(),
-            y.to_string(),
-            true,
-        )
-        .unwrap();
-        putpng::crop::crop_all(paths.iter().map(|s| s.clone()), &crc).unwrap();
-        for path in paths {
+    fn push_from(&mut self, rendered_dir: &Path, subdir: &str, index: usize) -> anyhow::Result<()> {
+        for path in sorted_paths(&rendered_dir.join(format!("{subdir}{index}")))? {
             self.push_lump(
                 &std::fs::read(&path).with_context(|| path.as_str().to_string())?,
-                &Path::new(&path).file_stem().unwrap().to_str().unwrap(),
-            )?;
+                Path::new(&path).file_stem().unwrap().to_str().unwrap(),
+            );
         }
         Ok(())
     }
@@ -49,97 +22,198 @@ trait Archive {
         &mut self,
         rendered_dir: &Path,
         name: &str,
-        sprite_prefix: &str,
+        sprites: &SpriteNames,
         index: usize,
-        crc: &Crc32,
     ) -> anyhow::Result<()> {
-        let sprite = sprite_prefix.to_skin_sprite();
-        let crouch_sprite = sprite_prefix.to_crouched_skin_sprite();
-        let mugshot = sprite_prefix.to_mugshot_sprite();
+        let SpriteNames {
+            skin: sprite,
+            crouch: crouch_sprite,
+            face: mugshot,
+            ..
+        } = sprites;
         self.push_lump(
             &format!(
                 "name = \"{name}\"\nsprite = {sprite}\ncrouchsprite = {crouch_sprite}\nface = {mugshot}\nscale = 0.5"
             )
             .into_bytes(),
             "S_SKIN",
-        )?;
-        self.grab_from(rendered_dir, "sprites", index, "w / 2", "h - 15", crc)?;
-        self.grab_from(
-            rendered_dir,
-            "crouch-sprites",
-            index,
-            "w / 2",
-            "h - 15",
-            crc,
-        )?;
-        self.grab_from(
-            rendered_dir,
-            "mugshot",
-            index,
-            "w / 2 - 18",
-            "h / 2 - 17",
-            crc,
-        )?;
+        );
+        self.push_player_from(rendered_dir, sprites, index)
+    }
 
-        Ok(())
+    fn push_player_from(
+        &mut self,
+        rendered_dir: &Path,
+        sprites: &SpriteNames,
+        index: usize,
+    ) -> anyhow::Result<()> {
+        self.push_from(rendered_dir, "sprites", index)?;
+        self.push_from(rendered_dir, "crouch-sprites", index)?;
+        self.push_mugshot_from(rendered_dir, sprites, index)
+    }
+
+    /// The status bar needs every face of the set, and a cached or kept render directory isn't
+    /// guaranteed to have them all.
+    fn push_mugshot_from(
+        &mut self,
+        rendered_dir: &Path,
+        sprites: &SpriteNames,
+        index: usize,
+    ) -> anyhow::Result<()> {
+        let subdir = rendered_dir.join(format!("mugshot{index}"));
+        let missing = sprites
+            .to_mugshot_lumps()
+            .into_iter()
+            .filter(|lump| {
+                !subdir
+                    .join(format!("{}.png", lump.replace("\\", "^")))
+                    .exists()
+            })
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "face set for {} is missing {}",
+                sprites.face,
+                missing.join(", ")
+            );
+        }
+        self.push_from(rendered_dir, "mugshot", index)
+    }
+}
+
+/// A PWAD assembled in memory, so writing it out can report failures.
+#[derive(Default)]
+struct Wad {
+    lumps: Vec<([u8; 8], Vec<u8>)>,
+}
+
+impl Wad {
+    fn to_bytes(&self) -> Vec<u8> {
+        const HEADER: usize = 12;
+        const ENTRY: usize = 16;
+
+        let directory = HEADER + self.lumps.len() * ENTRY;
+        let mut bytes = Vec::with_capacity(
+            directory + self.lumps.iter().map(|(_, data)| data.len()).sum::<usize>(),
+        );
+        bytes.extend_from_slice(b"PWAD");
+        bytes.extend_from_slice(&(self.lumps.len() as i32).to_le_bytes());
+        bytes.extend_from_slice(&(HEADER as i32).to_le_bytes());
+        let mut offset = directory;
+        for (name, data) in &self.lumps {
+            bytes.extend_from_slice(&(offset as i32).to_le_bytes());
+            bytes.extend_from_slice(&(data.len() as i32).to_le_bytes());
+            bytes.extend_from_slice(name);
+            offset += data.len();
+        }
+        for (_, data) in &self.lumps {
+            bytes.extend_from_slice(data);
+        }
+        bytes
+    }
+
+    fn save(&self, path: &Path) -> Result<(), ConvertError> {
+        std::fs::write(path, self.to_bytes()).map_err(ConvertError::io(path))
     }
 }
 
 impl Archive for Wad {
     fn new_archive() -> Self {
-        let mut wad = Wad::new();
-        wad.set_kind(WadKind::Pwad);
-        wad
+        Self::default()
     }
 
-    fn push_lump(&mut self, buffer: &Vec<u8>, name: &str) -> anyhow::Result<()> {
-        let name = name.replace("^", "\\");
-        self.add_lump_raw(LumpAdd::new(LumpAddKind::Back, &buffer, &name))?;
-        Ok(())
+    fn push_lump(&mut self, buffer: &[u8], name: &str) {
+        let mut lump_name = [0; 8];
+        for (byte, name_byte) in lump_name.iter_mut().zip(name.replace("^", "\\").bytes()) {
+            *byte = name_byte;
+        }
+        self.lumps.push((lump_name, buffer.to_vec()));
     }
 }
 
+const GRAB_OFFSETS: [(&str, &str, &str); 4] = [
+    ("sprites", "w / 2", "h - 15"),
+    ("crouch-sprites", "w / 2", "h - 15"),
+    ("mugshot", "w / 2 - 18", "h / 2 - 17"),
+    ("fist", "-w / 2 - 15", "-h / 2 + 3"),
+];
+
+pub fn grab_renders(rendered_dir: &Path, index: usize, crc: &Crc32) -> anyhow::Result<()> {
+    for (subdir, x, y) in GRAB_OFFSETS {
+        let dir = rendered_dir.join(format!("{subdir}{index}"));
+        if !dir.exists() {
+            continue;
+        }
+        let paths = sorted_paths(&dir)?;
+        putpng::grab::grab_all(paths.iter().cloned(), crc, x.into(), y.into(), true)
+            .map_err(|e| anyhow::anyhow!("grabbing {subdir}{index}: {e}"))?;
+        putpng::crop::crop_all(paths.iter().cloned(), crc)
+            .map_err(|e| anyhow::anyhow!("cropping {subdir}{index}: {e}"))?;
+    }
+    Ok(())
+}
+
+fn pack_failed(index: usize, name: &str, source: anyhow::Error) -> ConvertError {
+    ConvertError::PackFailed {
+        skin: Some(SkinRef {
+            index,
+            name: name.to_string(),
+        }),
+        source,
+    }
+}
+
+fn sorted_paths(dir: &Path) -> anyhow::Result<Vec<String>> {
+    let mut paths = vec![];
+    for entry in std::fs::read_dir(dir).with_context(|| dir.display().to_string())? {
+        paths.push(entry?.path().to_string_lossy().into_owned());
+    }
+    paths.sort();
+    Ok(paths)
+}
+
 pub fn produce_s_skin_wad(
     rendered_dir: &Path,
     produced_file: &Path,
-    names_and_sprite_prefixes: Vec<(String, String)>,
-    crc: &Crc32,
-) -> anyhow::Result<()> {
+    names_and_sprites: Vec<(String, SpriteNames)>,
+    _settings: &Settings,
+    progress: &dyn Fn(String),
+) -> Result<(), ConvertError> {
     let mut wad = Wad::new_archive();
-    for (index, (name, sprite_prefix)) in names_and_sprite_prefixes.into_iter().enumerate() {
-        wad.populate_s_skin(rendered_dir, &sprite_prefix, &name, index, crc)?;
+    for (index, (name, sprites)) in names_and_sprites.into_iter().enumerate() {
+        progress(format!("Packing {name}"));
+        wad.populate_s_skin(rendered_dir, &name, &sprites, index)
+            .map_err(|source| pack_failed(index, &name, source))?;
     }
-    wad.save(produced_file);
-    Ok(())
+    progress(format!("Writing {}", produced_file.display()));
+    wad.save(produced_file)
 }
 
 pub fn produce_s_skin_and_fist_wads(
     rendered_dir: &Path,
     produced_file: &Path,
-    names_and_sprite_prefixes: Vec<(String, String)>,
-    crc: &Crc32,
-) -> anyhow::Result<()> {
+    names_and_sprites: Vec<(String, SpriteNames)>,
+    settings: &Settings,
+    progress: &dyn Fn(String),
+) -> Result<(), ConvertError> {
     let mut wad = Wad::new_archive();
     let mut fist_wad = Wad::new_archive();
     let mut decorate = String::new();
     let mut index = 0;
-    fist_wad.push_lump(&vec![], "S_START")?;
-    for (name, sprite_prefix) in names_and_sprite_prefixes {
-        wad.populate_s_skin(rendered_dir, &name, &sprite_prefix, index, crc)?;
-        fist_wad.grab_from(
-            rendered_dir,
-            "fist",
-            index,
-            "-w / 2 - 15",
-            "-h / 2 + 3",
-            crc,
-        )?;
-        decorate += &generate_fist_decorate(&sprite_prefix.to_fist_sprite(), index);
+    fist_wad.push_lump(&[], "S_START");
+    for (name, sprites) in names_and_sprites {
+        progress(format!("Packing {name}"));
+        wad.populate_s_skin(rendered_dir, &name, &sprites, index)
+            .map_err(|source| pack_failed(index, &name, source))?;
+        fist_wad
+            .push_from(rendered_dir, "fist", index)
+            .map_err(|source| pack_failed(index, &name, source))?;
+        decorate += &generate_fist_decorate(&sprites.fist, index, settings.fist_style);
         index += 1;
     }
     decorate.pop();
-    fist_wad.push_lump(&vec![], "S_END")?;
-    fist_wad.push_lump(&decorate.into_bytes(), "DECORATE")?;
+    fist_wad.push_lump(&[], "S_END");
+    fist_wad.push_lump(&decorate.into_bytes(), "DECORATE");
     let acs = indoc::formatdoc!(
         r#"
         #library "pickfist"
@@ -167,42 +241,52 @@ pub fn produce_s_skin_and_fist_wads(
         }}
         "#
     );
-    std::fs::write("pick-fist.acs", acs.clone()).unwrap();
-    std::process::Command::new(get_acc().unwrap())
-        .arg("pick-fist")
-        .output()
-        .unwrap();
-    fist_wad.push_lump(&acs.into_bytes(), "PICKFIST")?;
-    fist_wad.push_lump(&"PICKFIST".as_bytes().to_vec(), "LOADACS")?;
-    fist_wad.push_lump(&vec![], "A_START")?;
-    fist_wad.push_lump(&std::fs::read("pick-fist.o").unwrap(), "PICKFIST")?;
-    fist_wad.push_lump(&vec![], "A_END")?;
+    std::fs::write("pick-fist.acs", acs.clone()).map_err(|source| ConvertError::Io {
+        path: "pick-fist.acs".into(),
+        source,
+    })?;
+    compile_acs("pick-fist")?;
+    fist_wad.push_lump(&acs.into_bytes(), "PICKFIST");
+    fist_wad.push_lump("PICKFIST".as_bytes(), "LOADACS");
+    fist_wad.push_lump(&[], "A_START");
     fist_wad.push_lump(
-        &"[enu default]\nFIST = \"Fist\";\0".as_bytes().to_vec(),
-        "LANGUAGE",
-    )?;
-    std::fs::remove_file("pick-fist.acs")?;
-    std::fs::remove_file("pick-fist.o")?;
-    wad.save(produced_file);
-    fist_wad.save(produced_file.to_str().unwrap().replace('.', "-fist."));
-    Ok(())
+        &std::fs::read("pick-fist.o").map_err(|source| ConvertError::Io {
+            path: "pick-fist.o".into(),
+            source,
+        })?,
+        "PICKFIST",
+    );
+    fist_wad.push_lump(&[], "A_END");
+    fist_wad.push_lump("[enu default]\nFIST = \"Fist\";\0".as_bytes(), "LANGUAGE");
+    std::fs::remove_file("pick-fist.acs").map_err(ConvertError::io(Path::new("pick-fist.acs")))?;
+    std::fs::remove_file("pick-fist.o").map_err(ConvertError::io(Path::new("pick-fist.o")))?;
+    progress(format!("Writing {}", produced_file.display()));
+    wad.save(produced_file)?;
+    fist_wad.save(Path::new(
+        &produced_file.to_string_lossy().replace('.', "-fist."),
+    ))
 }
 
 pub fn produce_decorate_wad(
     rendered_dir: &Path,
     produced_file: &Path,
-    names_and_sprite_prefixes: Vec<(String, String)>,
-    crc: &Crc32,
-) -> anyhow::Result<()> {
+    names_and_sprites: Vec<(String, SpriteNames)>,
+    settings: &Settings,
+    progress: &dyn Fn(String),
+) -> Result<(), ConvertError> {
     let mut wad = Wad::new_archive();
     let mut decorate = String::new();
     let mut mapinfo = "GameInfo {\n    PlayerClasses = ".to_string();
-    wad.push_lump(&vec![], "S_START")?;
-    for (index, (name, sprite_prefix)) in names_and_sprite_prefixes.into_iter().enumerate() {
-        let sprite = sprite_prefix.to_skin_sprite().quoted();
-        let crouch_sprite = sprite_prefix.to_crouched_skin_sprite();
-        let mugshot = sprite_prefix.to_mugshot_sprite();
-        let fist = sprite_prefix.to_fist_sprite();
+    wad.push_lump(&[], "S_START");
+    for (index, (name, sprites)) in names_and_sprites.into_iter().enumerate() {
+        progress(format!("Packing {name}"));
+        let SpriteNames {
+            skin: sprite,
+            crouch: crouch_sprite,
+            fist,
+            face: mugshot,
+        } = &sprites;
+        let sprite = format!("\"{sprite}\"");
         decorate += &indoc::formatdoc!(
             r#"
             ACTOR Crafter{index} : DoomPlayer {{
@@ -276,48 +360,69 @@ pub fn produce_decorate_wad(
 
             "#
         );
-        decorate += &generate_fist_decorate(&fist, index);
+        decorate += &generate_fist_decorate(fist, index, settings.fist_style);
         mapinfo += &format!("\"Crafter{index}\", ");
-        wad.grab_from(rendered_dir, "sprites", index, "w / 2", "h - 15", crc)?;
-        wad.grab_from(
-            rendered_dir,
-            "crouch-sprites",
-            index,
-            "w / 2",
-            "h - 15",
-            crc,
-        )?;
-        wad.grab_from(
-            rendered_dir,
-            "mugshot",
-            index,
-            "w / 2 - 18",
-            "h / 2 - 17",
-            crc,
-        )?;
-        wad.grab_from(
-            rendered_dir,
-            "fist",
-            index,
-            "-w / 2 - 15",
-            "-h / 2 + 3",
-            crc,
-        )?;
+        wad.push_player_from(rendered_dir, &sprites, index)
+            .and_then(|()| wad.push_from(rendered_dir, "fist", index))
+            .map_err(|source| pack_failed(index, &name, source))?;
     }
     decorate.pop();
     mapinfo.pop();
     mapinfo.pop();
     mapinfo += "\n}";
-    wad.push_lump(&vec![], "S_END")?;
-    wad.push_lump(&decorate.into_bytes(), "DECORATE")?;
-    wad.push_lump(&mapinfo.into_bytes(), "MAPINFO")?;
+    wad.push_lump(&[], "S_END");
+    wad.push_lump(&decorate.into_bytes(), "DECORATE");
+    wad.push_lump(&mapinfo.into_bytes(), "MAPINFO");
+
+    progress(format!("Writing {}", produced_file.display()));
+    wad.save(produced_file)
+}
 
-    wad.save(produced_file);
+fn compile_acs(script: &str) -> Result<(), ConvertError> {
+    let external_tool = |source| ConvertError::ExternalTool {
+        tool: "acc".into(),
+        source,
+    };
+    let acc = get_acc().ok_or_else(|| external_tool(anyhow::anyhow!("acc was not found")))?;
+    let output = std::process::Command::new(&acc)
+        .arg(script)
+        .output()
+        .map_err(|e| external_tool(anyhow::Error::new(e).context(acc.display().to_string())))?;
+    if !output.status.success() {
+        return Err(external_tool(anyhow::anyhow!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
     Ok(())
 }
 
-fn generate_fist_decorate(sprite: &str, index: usize) -> String {
+fn generate_fist_decorate(sprite: &str, index: usize, fist_style: FistStyle) -> String {
     let sprite = format!("\"{sprite}\"");
+    let fire = match fist_style {
+        FistStyle::Single => indoc::formatdoc!(
+            r#"
+            Fire:
+                {sprite} B 2 A_Punch
+                {sprite} CDEFGHI 2
+                TNT1 A 0 A_Refire
+                Goto Ready"#
+        ),
+        FistStyle::Alternating => indoc::formatdoc!(
+            r#"
+            Fire:
+                {sprite} B 2 A_Punch
+                {sprite} CDEFGHI 2
+                TNT1 A 0 A_Refire("LeftFire")
+                Goto Ready
+            LeftFire:
+                {sprite} J 2 A_Punch
+                {sprite} KLMNOPQ 2
+                TNT1 A 0 A_Refire
+                Goto Ready"#
+        ),
+    }
+    .replace('\n', "\n    ");
     indoc::formatdoc!(
         r#"
         ACTOR Fist{index} : Weapon replaces Fist {{
@@ -337,11 +442,7 @@ fn generate_fist_decorate(sprite: &str, index: usize) -> String {
             Deselect:
                 {sprite} A 1 A_Lower
                 Loop
-            Fire:
-                {sprite} B 2 A_Punch
-                {sprite} CDEFGHI 2
-                TNT1 A 0 A_Refire
-                Goto Ready
+            {fire}
             }}
         }}
 
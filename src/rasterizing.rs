@@ -0,0 +1,124 @@
+use crate::rendering::Model;
+use three_d::*;
+
+pub fn rasterize<'a>(
+    models: impl IntoIterator<Item = &'a Model>,
+    camera: &Camera,
+    width: u32,
+    height: u32,
+) -> Vec<[u8; 4]> {
+    let mut target = Target {
+        width,
+        height,
+        pixels: vec![[0; 4]; (width * height) as usize],
+        depths: vec![1.0; (width * height) as usize],
+    };
+    let view_projection = camera.projection() * camera.view();
+    for model in models {
+        let matrix = view_projection * model.transformation;
+        let vertices = model
+            .positions
+            .map(|position| matrix * position.extend(1.0));
+        if vertices.iter().any(|vertex| vertex.w <= 0.0) {
+            continue;
+        }
+        let color = model.color.to_linear_srgb();
+        for indices in Model::INDICES {
+            target.draw_triangle(model, indices.map(|i| (vertices[i], Model::UVS[i])), color);
+        }
+    }
+    target.pixels
+}
+
+struct Target {
+    width: u32,
+    height: u32,
+    pixels: Vec<[u8; 4]>,
+    depths: Vec<f32>,
+}
+
+impl Target {
+    fn draw_triangle(&mut self, model: &Model, vertices: [(Vec4, Vec2); 3], color: Vec4) {
+        let screen = vertices.map(|(clip, _)| {
+            vec3(
+                (clip.x / clip.w + 1.0) / 2.0 * self.width as f32,
+                (1.0 - clip.y / clip.w) / 2.0 * self.height as f32,
+                (clip.z / clip.w + 1.0) / 2.0,
+            )
+        });
+        let area = edge(screen[0], screen[1], screen[2]);
+        if area == 0.0 {
+            return;
+        }
+
+        let left = screen.iter().map(|v| v.x).fold(f32::MAX, f32::min).max(0.0) as u32;
+        let top = screen.iter().map(|v| v.y).fold(f32::MAX, f32::min).max(0.0) as u32;
+        let right = screen.iter().map(|v| v.x).fold(f32::MIN, f32::max).ceil();
+        let bottom = screen.iter().map(|v| v.y).fold(f32::MIN, f32::max).ceil();
+        let right = (right.max(0.0) as u32).min(self.width);
+        let bottom = (bottom.max(0.0) as u32).min(self.height);
+
+        for y in top..bottom {
+            for x in left..right {
+                let point = vec3(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+                let weights = [
+                    edge(screen[1], screen[2], point) / area,
+                    edge(screen[2], screen[0], point) / area,
+                    edge(screen[0], screen[1], point) / area,
+                ];
+                if weights.iter().any(|&weight| weight < 0.0) {
+                    continue;
+                }
+
+                let perspective = (0..3).map(|i| weights[i] / vertices[i].0.w);
+                let total = perspective.clone().sum::<f32>();
+                let uv = perspective
+                    .enumerate()
+                    .map(|(i, weight)| vertices[i].1 * weight)
+                    .sum::<Vec2>()
+                    / total;
+                // Fully transparent texels are discarded, so they don't hide what is behind them.
+                let Some(pixel) = shade(model, uv, color) else {
+                    continue;
+                };
+
+                let depth = (0..3).map(|i| weights[i] * screen[i].z).sum::<f32>();
+                let index = (y * self.width + x) as usize;
+                if !(0.0..self.depths[index]).contains(&depth) {
+                    continue;
+                }
+                self.depths[index] = depth;
+                self.pixels[index] = pixel;
+            }
+        }
+    }
+}
+
+fn edge(a: Vec3, b: Vec3, point: Vec3) -> f32 {
+    (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x)
+}
+
+fn shade(model: &Model, uv: Vec2, color: Vec4) -> Option<[u8; 4]> {
+    let x = ((uv.x * model.width as f32) as u32).min(model.width - 1);
+    let y = ((uv.y * model.height as f32) as u32).min(model.height - 1);
+    let texel = model.pixels[(y * model.width + x) as usize];
+    if texel[3] == 0 {
+        return None;
+    }
+    let channel = |i: usize| color[i] * texel[i] as f32 / 255.0;
+    Some([
+        to_srgb(channel(0)),
+        to_srgb(channel(1)),
+        to_srgb(channel(2)),
+        (channel(3) * 255.0).round() as u8,
+    ])
+}
+
+fn to_srgb(channel: f32) -> u8 {
+    let channel = if channel < 0.0031308 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    };
+    (channel.clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -1,18 +1,36 @@
-use crate::converting::{Rendering, SpritePrefix};
+use crate::{
+    converting::{Backdrop, FistStyle, Rendering, Settings, SpriteNames},
+    rasterizing::rasterize,
+};
 use anyhow::Context as WithContext;
 use image::{DynamicImage, GenericImageView};
 use std::{f32::consts::PI, path::Path};
 use three_d::*;
 
-pub struct TargetTexture {
-    texture: Texture2D,
-    depth: DepthTexture2D,
+/// How far the camera backs away from the front view to frame the mugshot.
+const MUGSHOT_PULLBACK: f32 = 10.0;
+
+pub enum TargetTexture {
+    Gpu {
+        texture: Texture2D,
+        depth: DepthTexture2D,
+    },
+    Cpu {
+        width: u32,
+        height: u32,
+    },
 }
 
 impl TargetTexture {
     pub fn new(rendering: &Rendering) -> Self {
+        let Some(context) = &rendering.context else {
+            return Self::Cpu {
+                width: rendering.viewport.width,
+                height: rendering.viewport.height,
+            };
+        };
         let texture = Texture2D::new_empty::<[u8; 4]>(
-            &rendering.context,
+            context,
             rendering.viewport.width,
             rendering.viewport.height,
             Interpolation::Nearest,
@@ -22,23 +40,23 @@ impl TargetTexture {
             Wrapping::ClampToEdge,
         );
         let depth = DepthTexture2D::new::<f32>(
-            &rendering.context,
+            context,
             rendering.viewport.width,
             rendering.viewport.height,
             Wrapping::ClampToEdge,
             Wrapping::ClampToEdge,
         );
 
-        Self { texture, depth }
+        Self::Gpu { texture, depth }
     }
 }
 
-trait AsObjects {
-    fn as_objects(&self) -> impl IntoIterator<Item = impl Object> + Clone;
+trait AsModels {
+    fn as_models(&self) -> impl IntoIterator<Item = &Model> + Clone;
 }
 
-impl AsObjects for Skin {
-    fn as_objects(&self) -> impl IntoIterator<Item = impl Object> + Clone {
+impl AsModels for Skin {
+    fn as_models(&self) -> impl IntoIterator<Item = &Model> + Clone {
         self.limbs
             .iter()
             .flat_map(|p| &p.faces)
@@ -47,8 +65,19 @@ impl AsObjects for Skin {
     }
 }
 
-impl AsObjects for (&Limb, &Trim) {
-    fn as_objects(&self) -> impl IntoIterator<Item = impl Object> + Clone {
+impl AsModels for [(&Limb, &Trim)] {
+    fn as_models(&self) -> impl IntoIterator<Item = &Model> + Clone {
+        self.iter().flat_map(|(limb, trim)| {
+            limb.faces
+                .iter()
+                .map(|f| &f.model)
+                .chain(trim.texels.iter().map(|t| &t.model))
+        })
+    }
+}
+
+impl AsModels for (&Limb, &Trim) {
+    fn as_models(&self) -> impl IntoIterator<Item = &Model> + Clone {
         self.0
             .faces
             .iter()
@@ -58,49 +87,33 @@ impl AsObjects for (&Limb, &Trim) {
 }
 
 fn create_subdir(rendered_dir: &Path, subdir: &str, index: usize) -> anyhow::Result<()> {
-    std::fs::create_dir(rendered_dir.join(&format!("{subdir}{index}")))
+    std::fs::create_dir(rendered_dir.join(format!("{subdir}{index}")))
         .with_context(|| format!("subdirectory {subdir}{index}"))
 }
 
 pub fn render_skin(
     atlas: &DynamicImage,
     rendered_dir: &Path,
-    sprite_prefix: &str,
+    sprites: &SpriteNames,
     rendering: &mut Rendering,
     index: usize,
+    settings: &Settings,
 ) -> anyhow::Result<()> {
-    let sprite = sprite_prefix.to_skin_sprite();
-    let mut target = TargetTexture::new(&rendering);
-    let mut skin = Skin::load(atlas, &sprite, &rendering.context);
+    let sprite = sprites.skin.as_str();
+    let mut target = TargetTexture::new(rendering);
+    let mut skin = Skin::load(atlas, sprite, rendering.context.as_deref());
 
     create_subdir(rendered_dir, "sprites", index)?;
     for frame_index in 'A'..='W' {
-        match frame_index {
-            'A' | 'C' => skin.reset(),
-            'B' => skin.flap_right_arm_and_leg(),
-            'D' => skin.flap_left_arm_and_leg(),
-            'E' | 'F' => skin.punch(frame_index),
-            'G' => skin.apply_red(70),
-            'H'..='N' => skin.rotate_around(
-                vec3(0.0, -11.0, 0.0),
-                -Vec3::unit_z(),
-                (90.0 / 7.0) * (frame_index.offset_from('H') + 1) as f32,
-            ),
-            'O'..='W' => skin.rotate_around(
-                vec3(0.0, -11.0, 0.0),
-                -Vec3::unit_z(),
-                (90.0 / 9.0) * (frame_index.offset_from('O') + 1) as f32,
-            ),
-            _ => unreachable!(),
-        }
+        skin.pose(frame_index, settings);
 
-        let objects = skin.as_objects();
+        let objects = skin.as_models();
         match frame_index {
             'A'..='G' => {
                 for rotation in 1..=8 {
                     render_frame(
                         objects.clone(),
-                        &rendering,
+                        rendering,
                         &mut target,
                         rendered_dir,
                         "sprites",
@@ -115,7 +128,7 @@ pub fn render_skin(
             'H'..='W' => {
                 render_frame(
                     objects.clone(),
-                    &rendering,
+                    rendering,
                     &mut target,
                     rendered_dir,
                     "sprites",
@@ -133,41 +146,60 @@ pub fn render_skin(
 pub fn render_skin_with_crouch(
     atlas: &DynamicImage,
     rendered_dir: &Path,
-    sprite_prefix: &str,
+    sprites: &SpriteNames,
     rendering: &mut Rendering,
     index: usize,
+    settings: &Settings,
 ) -> anyhow::Result<()> {
-    render_skin(atlas, rendered_dir, sprite_prefix, rendering, index)?;
+    render_skin(atlas, rendered_dir, sprites, rendering, index, settings)?;
 
-    let sprite = sprite_prefix.to_crouched_skin_sprite();
-    let mut target = TargetTexture::new(&rendering);
-    let mut skin = Skin::load_crouched(atlas, &sprite, &rendering.context);
+    let sprite = sprites.crouch.as_str();
+    let mut target = TargetTexture::new(rendering);
+    let mut skin = Skin::load_crouched(atlas, sprite, rendering.context.as_deref());
 
     create_subdir(rendered_dir, "crouch-sprites", index)?;
-    for frame_index in 'A'..='G' {
+    for frame_index in 'A'..='W' {
         match frame_index {
             'A' | 'C' => skin.reset_crouched(),
             'B' => skin.flap_right_arm_and_leg_crouched(),
             'D' => skin.flap_left_arm_and_leg_crouched(),
             'E' | 'F' => skin.punch_crouched(frame_index),
-            'G' => skin.apply_red_crouched(70),
+            'G' => skin.apply_tint_crouched(settings.pain_tint()),
+            'H'..='N' => skin.fall_crouched(frame_index),
+            'O'..='W' => skin.explode_crouched(frame_index),
             _ => unreachable!(),
         }
 
-        let objects = skin.as_objects();
-        for rotation in 1..=8 {
-            render_frame(
-                objects.clone(),
-                rendering,
-                &mut target,
-                rendered_dir,
-                "crouch-sprites",
-                index,
-                &format!("{sprite}{frame_index}{rotation}"),
-            )?;
-            rendering
-                .camera
-                .rotate_around_with_fixed_up(Vec3::zero(), -PI / 4.0, 0.0);
+        let objects = skin.as_models();
+        match frame_index {
+            'A'..='G' => {
+                for rotation in 1..=8 {
+                    render_frame(
+                        objects.clone(),
+                        rendering,
+                        &mut target,
+                        rendered_dir,
+                        "crouch-sprites",
+                        index,
+                        &format!("{sprite}{frame_index}{rotation}"),
+                    )?;
+                    rendering
+                        .camera
+                        .rotate_around_with_fixed_up(Vec3::zero(), -PI / 4.0, 0.0);
+                }
+            }
+            'H'..='W' => {
+                render_frame(
+                    objects.clone(),
+                    rendering,
+                    &mut target,
+                    rendered_dir,
+                    "crouch-sprites",
+                    index,
+                    &format!("{sprite}{frame_index}0"),
+                )?;
+            }
+            _ => unreachable!(),
         }
     }
 
@@ -177,31 +209,59 @@ pub fn render_skin_with_crouch(
 pub fn render_mugshot(
     atlas: &DynamicImage,
     rendered_dir: &Path,
-    sprite_prefix: &str,
+    sprites: &SpriteNames,
     rendering: &mut Rendering,
     index: usize,
+    settings: &Settings,
 ) -> anyhow::Result<()> {
-    rendering.camera.translate(Vec3::unit_z() * 10.0);
-    let sprite = sprite_prefix.to_mugshot_sprite();
-    let mut target = TargetTexture::new(&rendering);
+    rendering
+        .camera
+        .translate(Vec3::unit_z() * MUGSHOT_PULLBACK);
+    let sprite = sprites.face.as_str();
+    let mut target = TargetTexture::new(rendering);
     let mut head = Limb::load(
         atlas,
         "head".into(),
         Patch::HEAD,
         Vec3::zero(),
-        &rendering.context,
+        rendering.context.as_deref(),
     );
     let mut helmet = Trim::load(
         atlas,
         "helmet".into(),
         Patch::HELMET,
         Vec3::zero(),
-        &rendering.context,
+        rendering.context.as_deref(),
     );
+    let shoulders = if settings.mugshot_shoulders {
+        load_shoulders(atlas, rendering.context.as_deref())
+    } else {
+        vec![]
+    };
     let suffixes = ["DEAD", "EVL", "GOD", "KILL", "OUCH", "ST", "TL", "TR"];
 
     create_subdir(rendered_dir, "mugshot", index)?;
     for suffix in suffixes {
+        if settings.mugshot_expressions {
+            let expression = match suffix {
+                "DEAD" => Some(Expression::Dead),
+                "EVL" => Some(Expression::Grin),
+                "OUCH" => Some(Expression::Squint),
+                _ => None,
+            };
+            let face = match expression {
+                Some(expression) => &expression.paint(atlas),
+                None => atlas,
+            };
+            head = Limb::load(
+                face,
+                "head".into(),
+                Patch::HEAD,
+                Vec3::zero(),
+                rendering.context.as_deref(),
+            );
+        }
+
         match suffix {
             "DEAD" => {
                 let color = Srgba::new(64, 64, 64, 255);
@@ -239,7 +299,7 @@ pub fn render_mugshot(
 
         match suffix {
             "DEAD" | "GOD" => render_frame(
-                (&head, &helmet).as_objects(),
+                bust(&head, &helmet, &shoulders).as_models(),
                 rendering,
                 &mut target,
                 rendered_dir,
@@ -249,12 +309,12 @@ pub fn render_mugshot(
             )?,
             "EVL" | "KILL" | "OUCH" => {
                 for i in 0..5 {
-                    let saturation = 255 / (i + 1);
-                    head.apply_red(saturation);
-                    helmet.apply_red(saturation);
+                    let tint = settings.damage_tint(i);
+                    head.apply_color(tint);
+                    helmet.apply_color(tint);
                     render_frame(
-                        (&head, &helmet).as_objects(),
-                        &rendering,
+                        bust(&head, &helmet, &shoulders).as_models(),
+                        rendering,
                         &mut target,
                         rendered_dir,
                         "mugshot",
@@ -268,9 +328,9 @@ pub fn render_mugshot(
             }
             "ST" => {
                 for x in 0..5 {
-                    let saturation = 255 / (x + 1);
-                    head.apply_red(saturation);
-                    helmet.apply_red(saturation);
+                    let tint = settings.damage_tint(x);
+                    head.apply_color(tint);
+                    helmet.apply_color(tint);
                     for y in 0..3 {
                         let angle = match y {
                             0 => 15.0,
@@ -282,8 +342,8 @@ pub fn render_mugshot(
                         head.rotate_around(Vec3::zero(), &axis_angle);
                         helmet.rotate_around(Vec3::zero(), &axis_angle);
                         render_frame(
-                            (&head, &helmet).as_objects(),
-                            &rendering,
+                            bust(&head, &helmet, &shoulders).as_models(),
+                            rendering,
                             &mut target,
                             rendered_dir,
                             "mugshot",
@@ -295,12 +355,12 @@ pub fn render_mugshot(
             }
             "TL" | "TR" => {
                 for i in 0..5 {
-                    let saturation = 255 / (i + 1);
-                    head.apply_red(saturation);
-                    helmet.apply_red(saturation);
+                    let tint = settings.damage_tint(i);
+                    head.apply_color(tint);
+                    helmet.apply_color(tint);
                     render_frame(
-                        (&head, &helmet).as_objects(),
-                        &rendering,
+                        bust(&head, &helmet, &shoulders).as_models(),
+                        rendering,
                         &mut target,
                         rendered_dir,
                         "mugshot",
@@ -312,79 +372,270 @@ pub fn render_mugshot(
             _ => unreachable!(),
         }
     }
+
+    if settings.mugshot_shoulders || settings.mugshot_backdrop != Backdrop::None {
+        frame_mugshots(rendered_dir, index, settings.mugshot_backdrop)?;
+    }
+    Ok(())
+}
+
+fn load_shoulders(atlas: &DynamicImage, context: Option<&Context>) -> Vec<(Limb, Trim)> {
+    let (right_arm, right_sleeve, left_arm, left_sleeve, arm_offset) = if is_slim(atlas) {
+        (
+            Patch::SLIM_RIGHT_ARM,
+            Patch::SLIM_RIGHT_SLEEVE,
+            Patch::SLIM_LEFT_ARM,
+            Patch::SLIM_LEFT_SLEEVE,
+            5.5,
+        )
+    } else {
+        (
+            Patch::RIGHT_ARM,
+            Patch::RIGHT_SLEEVE,
+            Patch::LEFT_ARM,
+            Patch::LEFT_SLEEVE,
+            6.0,
+        )
+    };
+    let torso = vec3(0.0, -9.0, 0.0);
+    let right = vec3(-arm_offset, -9.0, 0.0);
+    let left = vec3(arm_offset, -9.0, 0.0);
+    vec![
+        (
+            Limb::load(atlas, "torso".into(), Patch::TORSO, torso, context),
+            Trim::load(atlas, "shirt".into(), Patch::SHIRT, torso, context),
+        ),
+        (
+            Limb::load(atlas, "rightArm".into(), right_arm, right, context),
+            Trim::load(atlas, "rightSleeve".into(), right_sleeve, right, context),
+        ),
+        (
+            Limb::load(atlas, "leftArm".into(), left_arm, left, context),
+            Trim::load(atlas, "leftSleeve".into(), left_sleeve, left, context),
+        ),
+    ]
+}
+
+fn bust<'a>(
+    head: &'a Limb,
+    helmet: &'a Trim,
+    shoulders: &'a [(Limb, Trim)],
+) -> Vec<(&'a Limb, &'a Trim)> {
+    std::iter::once((head, helmet))
+        .chain(shoulders.iter().map(|(limb, trim)| (limb, trim)))
+        .collect()
+}
+
+fn frame_mugshots(rendered_dir: &Path, index: usize, backdrop: Backdrop) -> anyhow::Result<()> {
+    const WIDTH: u32 = 24;
+    const HEIGHT: u32 = 29;
+
+    let subdir = rendered_dir.join(format!("mugshot{index}"));
+    for entry in
+        std::fs::read_dir(&subdir).with_context(|| format!("subdirectory mugshot{index}"))?
+    {
+        let path = entry?.path();
+        let mut image = image::open(&path)
+            .with_context(|| path.display().to_string())?
+            .to_rgba8();
+        let left = image.width() / 2 - WIDTH / 2;
+        let top = image.height() / 2 - 12;
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            if !(left..left + WIDTH).contains(&x) || !(top..top + HEIGHT).contains(&y) {
+                *pixel = image::Rgba([0, 0, 0, 0]);
+            } else if let Some(color) = backdrop.color_at((y - top) as f32 / (HEIGHT - 1) as f32) {
+                let alpha = pixel[3] as f32 / 255.0;
+                for (channel, background) in pixel.0.iter_mut().zip(color) {
+                    *channel = (*channel as f32 * alpha + background as f32 * (1.0 - alpha)) as u8;
+                }
+                pixel[3] = 255;
+            }
+        }
+        image
+            .save(&path)
+            .with_context(|| path.display().to_string())?;
+    }
     Ok(())
 }
 
 pub fn render_fist(
     atlas: &DynamicImage,
     rendered_dir: &Path,
-    sprite_prefix: &str,
+    sprites: &SpriteNames,
     rendering: &mut Rendering,
     index: usize,
+    settings: &Settings,
 ) -> anyhow::Result<()> {
-    let sprite = sprite_prefix.to_fist_sprite();
-    let mut target = TargetTexture::new(&rendering);
+    let sprite = sprites.fist.as_str();
+    let mut target = TargetTexture::new(rendering);
     let position = Vec3::unit_x() * 3.5;
+    let (right_arm_patch, right_sleeve_patch, left_arm_patch, left_sleeve_patch) = if is_slim(atlas)
+    {
+        (
+            Patch::SLIM_RIGHT_ARM,
+            Patch::SLIM_RIGHT_SLEEVE,
+            Patch::SLIM_LEFT_ARM,
+            Patch::SLIM_LEFT_SLEEVE,
+        )
+    } else {
+        (
+            Patch::RIGHT_ARM,
+            Patch::RIGHT_SLEEVE,
+            Patch::LEFT_ARM,
+            Patch::LEFT_SLEEVE,
+        )
+    };
     let mut arm = Limb::load(
         atlas,
         "arm".into(),
-        Patch::RIGHT_ARM,
+        right_arm_patch,
         position,
-        &rendering.context,
+        rendering.context.as_deref(),
     );
     let mut sleeve = Trim::load(
         atlas,
         "arm".into(),
-        Patch::RIGHT_SLEEVE,
+        right_sleeve_patch,
         position,
-        &rendering.context,
+        rendering.context.as_deref(),
+    );
+    let mut left_arm = Limb::load(
+        atlas,
+        "leftArm".into(),
+        left_arm_patch,
+        -position,
+        rendering.context.as_deref(),
+    );
+    let mut left_sleeve = Trim::load(
+        atlas,
+        "leftArm".into(),
+        left_sleeve_patch,
+        -position,
+        rendering.context.as_deref(),
     );
 
     let delta = 28.0;
 
+    // The fist was framed from the mugshot camera, so it starts from there whatever ran before.
+    rendering.reset_camera();
+    rendering
+        .camera
+        .translate(Vec3::unit_z() * MUGSHOT_PULLBACK);
     rendering.camera.rotate_around(Vec3::zero(), PI, 0.0);
     rendering.camera.translate(Vec3::unit_z() * -delta);
 
+    let last_frame = match settings.fist_style {
+        FistStyle::Single => 'I',
+        FistStyle::Alternating => 'Q',
+    };
+
     create_subdir(rendered_dir, "fist", index)?;
-    for frame_index in 'A'..='I' {
-        let rotation = [
-            (
-                Vec3::unit_x(),
-                match frame_index {
-                    'A' => 130.0,
-                    'B' => 135.0,
-                    'C' => 125.0,
-                    'D' => 115.0,
-                    'E' => 105.0,
-                    'F' => 110.0,
-                    'G' => 115.0,
-                    'H' => 120.0,
-                    'I' => 125.0,
-                    _ => unreachable!(),
-                },
+    for frame_index in 'A'..=last_frame {
+        let (right_angle, left_angle) = match frame_index {
+            'A'..='I' => (punch_angle(frame_index), punch_angle('A')),
+            'J'..='Q' => (
+                punch_angle('A'),
+                punch_angle(char::from_u32(frame_index as u32 - 8).unwrap()),
             ),
-            (Vec3::unit_y(), -115.0),
-        ];
+            _ => unreachable!(),
+        };
         let pivot = Vec3::zero();
+        let rotation = [(Vec3::unit_x(), right_angle), (Vec3::unit_y(), -115.0)];
         arm.rotate_around(pivot, &rotation);
         sleeve.rotate_around(pivot, &rotation);
-
-        render_frame(
-            (&arm, &sleeve).as_objects(),
-            &rendering,
-            &mut target,
-            rendered_dir,
-            "fist",
-            index,
-            &format!("{sprite}{frame_index}0"),
-        )?;
+        let rotation = [(Vec3::unit_x(), left_angle), (Vec3::unit_y(), 115.0)];
+        left_arm.rotate_around(pivot, &rotation);
+        left_sleeve.rotate_around(pivot, &rotation);
+
+        let file_stem = format!("{sprite}{frame_index}0");
+        match settings.fist_style {
+            FistStyle::Single => render_frame(
+                (&arm, &sleeve).as_models(),
+                rendering,
+                &mut target,
+                rendered_dir,
+                "fist",
+                index,
+                &file_stem,
+            )?,
+            FistStyle::Alternating => render_frame(
+                (&arm, &sleeve)
+                    .as_models()
+                    .into_iter()
+                    .chain((&left_arm, &left_sleeve).as_models()),
+                rendering,
+                &mut target,
+                rendered_dir,
+                "fist",
+                index,
+                &file_stem,
+            )?,
+        }
     }
 
     Ok(())
 }
 
-fn render_frame(
-    objects: impl IntoIterator<Item = impl Object>,
+fn punch_angle(frame_index: char) -> f32 {
+    match frame_index {
+        'A' => 130.0,
+        'B' => 135.0,
+        'C' => 125.0,
+        'D' => 115.0,
+        'E' => 105.0,
+        'F' => 110.0,
+        'G' => 115.0,
+        'H' => 120.0,
+        'I' => 125.0,
+        _ => unreachable!(),
+    }
+}
+
+pub fn render_sprite(
+    atlas: &DynamicImage,
+    rendering: &Rendering,
+    frame_index: char,
+    rotation: u8,
+    settings: &Settings,
+) -> anyhow::Result<image::RgbaImage> {
+    let mut target = TargetTexture::new(rendering);
+    let mut skin = Skin::load(atlas, "preview", rendering.context.as_deref());
+    skin.pose(frame_index, settings);
+
+    let mut camera = rendering.camera.clone();
+    camera.rotate_around_with_fixed_up(Vec3::zero(), -PI / 4.0 * rotation as f32, 0.0);
+    let pixels = render_pixels(skin.as_models(), &camera, &mut target);
+
+    image::RgbaImage::from_raw(
+        rendering.viewport.width,
+        rendering.viewport.height,
+        pixels.into_iter().flatten().collect(),
+    )
+    .context("rendered frame has the wrong size")
+}
+
+fn render_pixels<'a>(
+    models: impl IntoIterator<Item = &'a Model>,
+    camera: &Camera,
+    target: &mut TargetTexture,
+) -> Vec<[u8; 4]> {
+    match target {
+        TargetTexture::Gpu { texture, depth } => {
+            RenderTarget::new(texture.as_color_target(None), depth.as_depth_target())
+                .clear(ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0))
+                .render(
+                    camera,
+                    models.into_iter().filter_map(|model| model.gpu.as_ref()),
+                    &[],
+                )
+                .read_color()
+        }
+        TargetTexture::Cpu { width, height } => rasterize(models, camera, *width, *height),
+    }
+}
+
+fn render_frame<'a>(
+    models: impl IntoIterator<Item = &'a Model>,
     rendering: &Rendering,
     target: &mut TargetTexture,
     rendered_dir: &Path,
@@ -393,13 +644,7 @@ fn render_frame(
     file_stem: &str,
 ) -> anyhow::Result<()> {
     let file_name = file_stem.replace("\\", "^");
-    let pixels = RenderTarget::new(
-        target.texture.as_color_target(None),
-        target.depth.as_depth_target(),
-    )
-    .clear(ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0))
-    .render(&rendering.camera, objects, &[])
-    .read_color();
+    let pixels = render_pixels(models, &rendering.camera, target);
 
     use three_d_asset::io::Serialize;
 
@@ -420,6 +665,117 @@ fn render_frame(
     Ok(())
 }
 
+#[derive(Clone, Copy)]
+enum Expression {
+    Squint,
+    Grin,
+    Dead,
+}
+
+impl Expression {
+    fn paint(self, atlas: &DynamicImage) -> DynamicImage {
+        let mut face = atlas.to_rgba8();
+        let skin_tone = dominant_color(&face, Patch::HEAD);
+        let (row, left_eye, right_eye) = find_eyes(&face, Patch::HEAD, skin_tone);
+        let dark = skin_tone.map(|c| (c as f32 * 0.35) as u8);
+        let teeth = [235, 235, 220];
+        let mut put = |px: i32, py: i32, [r, g, b]: [u8; 3]| {
+            if (0..Patch::HEAD.width as i32).contains(&px)
+                && (0..Patch::HEAD.height as i32).contains(&py)
+            {
+                face.put_pixel(
+                    Patch::HEAD.x + px as u32,
+                    Patch::HEAD.y + py as u32,
+                    image::Rgba([r, g, b, 255]),
+                );
+            }
+        };
+
+        match self {
+            Expression::Squint => {
+                for column in left_eye.iter().chain(&right_eye) {
+                    put(*column, row, dark);
+                }
+            }
+            Expression::Grin => {
+                let mouth = (row + 2).min(6);
+                for column in 1..=6 {
+                    put(column, mouth, dark);
+                }
+                for column in 2..=5 {
+                    put(column, mouth + 1, teeth);
+                }
+            }
+            Expression::Dead => {
+                for eye in [&left_eye, &right_eye] {
+                    let centre = eye.iter().sum::<i32>() / eye.len() as i32;
+                    for d in -1..=1 {
+                        put(centre + d, row + d, dark);
+                        put(centre + d, row - d, dark);
+                    }
+                }
+            }
+        }
+
+        DynamicImage::ImageRgba8(face)
+    }
+}
+
+fn dominant_color(atlas: &image::RgbaImage, patch: Patch) -> [u8; 3] {
+    let mut counts = std::collections::BTreeMap::new();
+    for px in patch.x..patch.x + patch.width {
+        for py in patch.y..patch.y + patch.height {
+            let [r, g, b, _] = atlas.get_pixel(px, py).0;
+            *counts.entry([r, g, b]).or_insert(0) += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(color, _)| color)
+        .unwrap_or_default()
+}
+
+fn find_eyes(
+    atlas: &image::RgbaImage,
+    patch: Patch,
+    skin_tone: [u8; 3],
+) -> (i32, Vec<i32>, Vec<i32>) {
+    let half = patch.width as i32 / 2;
+    let contrasting = |row: i32, columns: std::ops::Range<i32>| {
+        columns
+            .filter(|column| {
+                let pixel = atlas
+                    .get_pixel(patch.x + *column as u32, patch.y + row as u32)
+                    .0;
+                pixel
+                    .iter()
+                    .zip(skin_tone)
+                    .map(|(a, b)| (*a as i32 - b as i32).abs())
+                    .sum::<i32>()
+                    > 150
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut eyes = (4, vec![1, 2], vec![5, 6]);
+    let mut best = 0;
+    for row in [4, 3, 5, 2, 6] {
+        let left = contrasting(row, 0..half);
+        let right = contrasting(row, half..patch.width as i32);
+        let score = left.len().min(right.len());
+        if score > best {
+            best = score;
+            eyes = (row, left, right);
+        }
+    }
+    eyes
+}
+
+fn is_slim(atlas: &DynamicImage) -> bool {
+    atlas.get_pixel(55, 20).0[3] < 10
+}
+
 struct Skin {
     limbs: [Limb; 6],
     trim: [Trim; 6],
@@ -455,8 +811,21 @@ impl Skin {
     const CROUCH_HEAD_OFFSET: Vec3 = vec3(0.0, -2.0, 3.0);
     const CROUCH_SLEEVE_OFFSET: Vec3 = vec3(0.0, 0.0, -2.0);
 
-    fn load(atlas: &image::DynamicImage, name: &str, context: &Context) -> Self {
-        if atlas.get_pixel(55, 20).0[3] < 10 {
+    const FALL_PIVOT: Vec3 = vec3(0.0, -11.0, 0.0);
+
+    const GROUND: f32 = -16.0;
+    // (landing offset with resting height as y, arc height, spin axis, spin angle)
+    const GIB_TRAJECTORIES: [(Vec3, f32, Vec3, f32); 6] = [
+        (vec3(6.0, 4.0, 2.0), 10.0, vec3(0.0, 0.0, 1.0), 360.0),
+        (vec3(0.0, 2.0, -2.0), 3.0, vec3(1.0, 0.0, 0.0), -90.0),
+        (vec3(-10.0, 2.0, 3.0), 6.0, vec3(0.0, 0.0, 1.0), 90.0),
+        (vec3(-18.0, 2.0, -1.0), 8.0, vec3(0.0, 0.0, 1.0), 270.0),
+        (vec3(10.0, 2.0, 3.0), 6.0, vec3(0.0, 0.0, 1.0), -90.0),
+        (vec3(18.0, 2.0, -1.0), 8.0, vec3(0.0, 0.0, 1.0), -270.0),
+    ];
+
+    fn load(atlas: &image::DynamicImage, name: &str, context: Option<&Context>) -> Self {
+        if is_slim(atlas) {
             Self {
                 limbs: [
                     Limb::load(
@@ -641,7 +1010,7 @@ impl Skin {
         }
     }
 
-    fn load_crouched(atlas: &image::DynamicImage, name: &str, context: &Context) -> Skin {
+    fn load_crouched(atlas: &image::DynamicImage, name: &str, context: Option<&Context>) -> Skin {
         let mut skin = Self::load(atlas, name, context);
         let torso = &mut skin.limbs[Skin::TORSO];
         let shirt = &mut skin.trim[Skin::SHIRT];
@@ -670,6 +1039,19 @@ impl Skin {
         skin
     }
 
+    fn pose(&mut self, frame_index: char, settings: &Settings) {
+        match frame_index {
+            'A' | 'C' => self.reset(),
+            'B' => self.flap_right_arm_and_leg(),
+            'D' => self.flap_left_arm_and_leg(),
+            'E' | 'F' => self.punch(frame_index),
+            'G' => self.apply_tint(settings.pain_tint()),
+            'H'..='N' => self.fall(frame_index),
+            'O'..='W' => self.explode(frame_index),
+            _ => unreachable!(),
+        }
+    }
+
     fn reset(&mut self) {
         for limb in self.limbs.iter_mut() {
             limb.set_transformation(Mat4::identity());
@@ -778,42 +1160,102 @@ impl Skin {
         self.trim[Self::RIGHT_ARM].rotate_around(Self::CROUCH_RIGHT_SHOULDER, &axes_angles);
     }
 
-    fn apply_red(&mut self, saturation: u8) {
+    fn apply_tint(&mut self, color: Srgba) {
         self.reset();
         for limb in self.limbs.iter_mut() {
-            limb.apply_red(saturation);
+            limb.apply_color(color);
         }
         for trim in self.trim.iter_mut() {
-            trim.apply_red(saturation);
+            trim.apply_color(color);
         }
     }
 
-    fn apply_red_crouched(&mut self, saturation: u8) {
+    fn apply_tint_crouched(&mut self, color: Srgba) {
         self.reset_crouched();
         for limb in self.limbs.iter_mut() {
-            limb.apply_red(saturation);
+            limb.apply_color(color);
         }
         for trim in self.trim.iter_mut() {
-            trim.apply_red(saturation);
+            trim.apply_color(color);
         }
     }
 
-    fn rotate_around(&mut self, pivot: Vec3, axis: Vec3, angle: f32) {
+    fn fall(&mut self, frame_index: char) {
+        let rotation = Self::fall_rotation(frame_index);
         for limb in self.limbs.iter_mut() {
-            let rotation = Mat4::from_translation(pivot)
-                * Mat4::from_axis_angle(axis, degrees(angle))
-                * Mat4::from_translation(-pivot);
-
             limb.set_transformation(rotation);
         }
         for trim in self.trim.iter_mut() {
-            let rotation = Mat4::from_translation(pivot)
-                * Mat4::from_axis_angle(axis, degrees(angle))
-                * Mat4::from_translation(-pivot);
-
             trim.set_transformation(rotation);
         }
     }
+
+    fn fall_crouched(&mut self, frame_index: char) {
+        let rotation = Self::fall_rotation(frame_index);
+        let crouch_rotation = rotation_around(Self::CROUCH_PIVOT, &Self::CROUCH_ROTATION);
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            match i {
+                Self::TORSO | Self::RIGHT_ARM | Self::LEFT_ARM => {
+                    limb.set_transformation(rotation * crouch_rotation)
+                }
+                _ => limb.set_transformation(rotation),
+            }
+        }
+        for (i, trim) in self.trim.iter_mut().enumerate() {
+            match i {
+                Self::SHIRT | Self::RIGHT_SLEEVE | Self::LEFT_SLEEVE => {
+                    trim.set_transformation(rotation * crouch_rotation)
+                }
+                _ => trim.set_transformation(rotation),
+            }
+        }
+    }
+
+    fn fall_rotation(frame_index: char) -> Mat4 {
+        let angle = (90.0 / 7.0) * (frame_index.offset_from('H') + 1) as f32;
+        rotation_around(Self::FALL_PIVOT, &[(-Vec3::unit_z(), angle)])
+    }
+
+    fn explode(&mut self, frame_index: char) {
+        self.explode_from(frame_index, |_| Mat4::identity());
+    }
+
+    fn explode_crouched(&mut self, frame_index: char) {
+        let crouch_rotation = rotation_around(Self::CROUCH_PIVOT, &Self::CROUCH_ROTATION);
+        self.explode_from(frame_index, |i| match i {
+            Self::TORSO | Self::RIGHT_ARM | Self::LEFT_ARM => crouch_rotation,
+            _ => Mat4::identity(),
+        });
+    }
+
+    fn explode_from(&mut self, frame_index: char, pose: impl Fn(usize) -> Mat4) {
+        let t = (frame_index.offset_from('O') + 1) as f32 / 9.0;
+        for (i, (landing, arc, axis, spin)) in Self::GIB_TRAJECTORIES.into_iter().enumerate() {
+            let pose = pose(i);
+            let centre = (pose * self.limbs[i].matrix).w.truncate();
+            let offset = vec3(
+                landing.x * t,
+                (Self::GROUND + landing.y - centre.y) * t + arc * 4.0 * t * (1.0 - t),
+                landing.z * t,
+            );
+            let transformation = Mat4::from_translation(centre + offset)
+                * Mat4::from_axis_angle(axis, degrees(spin * t))
+                * Mat4::from_translation(-centre)
+                * pose;
+
+            self.limbs[i].set_transformation(transformation);
+            self.trim[i].set_transformation(transformation);
+        }
+    }
+}
+
+fn rotation_around(pivot: Vec3, axes_angles: &[(Vec3, f32)]) -> Mat4 {
+    axes_angles
+        .iter()
+        .fold(Mat4::from_translation(pivot), |matrix, (axis, angle)| {
+            matrix * Mat4::from_axis_angle(*axis, degrees(*angle))
+        })
+        * Mat4::from_translation(-pivot)
 }
 
 pub struct Limb {
@@ -827,17 +1269,17 @@ impl Limb {
         name: String,
         patch: Patch,
         translation: Vec3,
-        context: &Context,
+        context: Option<&Context>,
     ) -> Self {
         let matrix = Mat4::from_translation(translation);
         let mut limb = Self {
             faces: [
-                Face::new_front(&name, &atlas, patch, context),
-                Face::new_right(&name, &atlas, patch, context),
-                Face::new_back(&name, &atlas, patch, context),
-                Face::new_left(&name, &atlas, patch, context),
-                Face::new_top(&name, &atlas, patch, context),
-                Face::new_bottom(&name, &atlas, patch, context),
+                Face::new_front(&name, atlas, patch, context),
+                Face::new_right(&name, atlas, patch, context),
+                Face::new_back(&name, atlas, patch, context),
+                Face::new_left(&name, atlas, patch, context),
+                Face::new_top(&name, atlas, patch, context),
+                Face::new_bottom(&name, atlas, patch, context),
             ],
             matrix,
         };
@@ -852,32 +1294,27 @@ impl Limb {
     }
 
     pub fn rotate_around(&mut self, pivot: Vec3, axes_angles: &[(Vec3, f32)]) {
-        let rotation = axes_angles
-            .into_iter()
-            .fold(Mat4::from_translation(pivot), |matrix, (axis, angle)| {
-                matrix * Mat4::from_axis_angle(*axis, degrees(*angle))
-            })
-            * Mat4::from_translation(-pivot);
-        self.set_transformation(rotation);
-    }
-
-    fn apply_red(&mut self, saturation: u8) {
-        self.apply_color(Srgba::new(255, saturation, saturation, 255))
+        self.set_transformation(rotation_around(pivot, axes_angles));
     }
 
     fn apply_color(&mut self, color: Srgba) {
         for face in self.faces.iter_mut() {
-            face.model.material.color = color;
+            face.model.set_color(color);
         }
     }
 }
 
 pub struct Face {
-    pub model: Gm<Mesh, ColorMaterial>,
+    pub model: Model,
 }
 
 impl Face {
-    fn new_front(name: &str, atlas: &image::DynamicImage, patch: Patch, context: &Context) -> Self {
+    fn new_front(
+        name: &str,
+        atlas: &image::DynamicImage,
+        patch: Patch,
+        context: Option<&Context>,
+    ) -> Self {
         let Vector3 { x, y, z } = patch.half_size();
         let positions = vec![
             vec3(-x, -y, z),
@@ -888,7 +1325,12 @@ impl Face {
         Self::new(name, atlas, patch, positions, 0, context)
     }
 
-    fn new_right(name: &str, atlas: &image::DynamicImage, patch: Patch, context: &Context) -> Self {
+    fn new_right(
+        name: &str,
+        atlas: &image::DynamicImage,
+        patch: Patch,
+        context: Option<&Context>,
+    ) -> Self {
         let Vector3 { x, y, z } = patch.half_size();
         let positions = vec![
             vec3(x, -y, z),
@@ -899,7 +1341,12 @@ impl Face {
         Self::new(name, atlas, patch.as_right(), positions, 1, context)
     }
 
-    fn new_back(name: &str, atlas: &image::DynamicImage, patch: Patch, context: &Context) -> Self {
+    fn new_back(
+        name: &str,
+        atlas: &image::DynamicImage,
+        patch: Patch,
+        context: Option<&Context>,
+    ) -> Self {
         let Vector3 { x, y, z } = patch.half_size();
         let positions = vec![
             vec3(x, -y, -z),
@@ -910,7 +1357,12 @@ impl Face {
         Self::new(name, atlas, patch.as_back(), positions, 2, context)
     }
 
-    fn new_left(name: &str, atlas: &image::DynamicImage, patch: Patch, context: &Context) -> Self {
+    fn new_left(
+        name: &str,
+        atlas: &image::DynamicImage,
+        patch: Patch,
+        context: Option<&Context>,
+    ) -> Self {
         let Vector3 { x, y, z } = patch.half_size();
         let positions = vec![
             vec3(-x, -y, -z),
@@ -921,7 +1373,12 @@ impl Face {
         Self::new(name, atlas, patch.as_left(), positions, 3, context)
     }
 
-    fn new_top(name: &str, atlas: &image::DynamicImage, patch: Patch, context: &Context) -> Self {
+    fn new_top(
+        name: &str,
+        atlas: &image::DynamicImage,
+        patch: Patch,
+        context: Option<&Context>,
+    ) -> Self {
         let Vector3 { x, y, z } = patch.half_size();
         let positions = vec![
             vec3(-x, y, z),
@@ -936,7 +1393,7 @@ impl Face {
         name: &str,
         atlas: &image::DynamicImage,
         patch: Patch,
-        context: &Context,
+        context: Option<&Context>,
     ) -> Self {
         let Vector3 { x, y, z } = patch.half_size();
         let positions = vec![
@@ -954,24 +1411,8 @@ impl Face {
         patch: Patch,
         positions: Vec<Vec3>,
         index: u8,
-        context: &Context,
+        context: Option<&Context>,
     ) -> Self {
-        let indices = vec![0, 1, 2, 2, 3, 0];
-
-        let uvs = vec![
-            Vec2::new(0.0, 1.0),
-            Vec2::new(1.0, 1.0),
-            Vec2::new(1.0, 0.0),
-            Vec2::new(0.0, 0.0),
-        ];
-
-        let mesh = CpuMesh {
-            positions: Positions::F32(positions),
-            indices: Indices::U8(indices),
-            uvs: Some(uvs),
-            ..Default::default()
-        };
-
         let sub_image =
             image::imageops::crop_imm(atlas, patch.x, patch.y, patch.width, patch.height)
                 .to_image();
@@ -983,27 +1424,14 @@ impl Face {
             let a = pixel[3];
             linear_data.push([r, g, b, a]);
         }
-        let data = TextureData::RgbaU8(linear_data);
-        let texture = CpuTexture {
-            name: format!("{name}_{index}"),
-            data,
-            width: patch.width,
-            height: patch.height,
-            min_filter: Interpolation::Nearest,
-            mag_filter: Interpolation::Nearest,
-            ..Default::default()
-        };
-
-        let material = ColorMaterial {
-            texture: Some(Texture2DRef::from_cpu_texture(&context, &texture)),
-            render_states: RenderStates {
-                cull: Cull::None,
-                ..Default::default()
-            },
-            ..Default::default()
-        };
-
-        let model = Gm::new(Mesh::new(context, &mesh), material);
+        let model = Model::new(
+            &format!("{name}_{index}"),
+            positions,
+            linear_data,
+            patch.width,
+            patch.height,
+            context,
+        );
 
         Self { model }
     }
@@ -1032,11 +1460,11 @@ impl Trim {
         name: String,
         patch: Patch,
         translation: Vec3,
-        context: &Context,
+        context: Option<&Context>,
     ) -> Self {
         let mut texels = vec![];
 
-        const ALPHA_MIN: u8 = 255;
+        const OPAQUE: u8 = u8::MAX;
 
         for px in 0..patch.width {
             for py in 0..patch.height {
@@ -1045,7 +1473,7 @@ impl Trim {
                 let tz = Self::UNIT / 2.0 * patch.depth as f32;
                 let pixel = atlas.get_pixel(px + patch.x, py + patch.y).0;
 
-                if pixel[3] >= ALPHA_MIN {
+                if pixel[3] == OPAQUE {
                     texels.push(Texel::new(
                         &name,
                         pixel,
@@ -1065,7 +1493,7 @@ impl Trim {
                 let tz = (right_patch.width as i32 / 2 - 1 - px as i32) as f32 * Self::UNIT;
                 let pixel = atlas.get_pixel(px + right_patch.x, py + right_patch.y).0;
 
-                if pixel[3] >= ALPHA_MIN {
+                if pixel[3] == OPAQUE {
                     texels.push(Texel::new(
                         &name,
                         pixel,
@@ -1085,7 +1513,7 @@ impl Trim {
                 let tz = -Self::UNIT / 2.0 * back_patch.depth as f32;
                 let pixel = atlas.get_pixel(px + back_patch.x, py + back_patch.y).0;
 
-                if pixel[3] >= ALPHA_MIN {
+                if pixel[3] == OPAQUE {
                     texels.push(Texel::new(
                         &name,
                         pixel,
@@ -1105,7 +1533,7 @@ impl Trim {
                 let tz = (px as i32 - left_patch.width as i32 / 2) as f32 * Self::UNIT;
                 let pixel = atlas.get_pixel(px + left_patch.x, py + left_patch.y).0;
 
-                if pixel[3] >= ALPHA_MIN {
+                if pixel[3] == OPAQUE {
                     texels.push(Texel::new(
                         &name,
                         pixel,
@@ -1125,7 +1553,7 @@ impl Trim {
                 let tz = (py as i32 - top_patch.height as i32 / 2) as f32 * Self::UNIT;
                 let pixel = atlas.get_pixel(px + top_patch.x, py + top_patch.y).0;
 
-                if pixel[3] >= ALPHA_MIN {
+                if pixel[3] == OPAQUE {
                     texels.push(Texel::new(
                         &name,
                         pixel,
@@ -1145,7 +1573,7 @@ impl Trim {
                 let tz = (bottom_patch.height as i32 / 2 - 1 - py as i32) as f32 * Self::UNIT;
                 let pixel = atlas.get_pixel(px + bottom_patch.x, py + bottom_patch.y).0;
 
-                if pixel[3] >= ALPHA_MIN {
+                if pixel[3] == OPAQUE {
                     texels.push(Texel::new(
                         &name,
                         pixel,
@@ -1170,28 +1598,18 @@ impl Trim {
     }
 
     pub fn rotate_around(&mut self, pivot: Vec3, axes_angles: &[(Vec3, f32)]) {
-        let rotation = axes_angles
-            .into_iter()
-            .fold(Mat4::from_translation(pivot), |matrix, (axis, angle)| {
-                matrix * Mat4::from_axis_angle(*axis, degrees(*angle))
-            })
-            * Mat4::from_translation(-pivot);
-        self.set_transformation(rotation);
-    }
-
-    fn apply_red(&mut self, saturation: u8) {
-        self.apply_color(Srgba::new(255, saturation, saturation, 255))
+        self.set_transformation(rotation_around(pivot, axes_angles));
     }
 
     fn apply_color(&mut self, color: Srgba) {
         for texel in self.texels.iter_mut() {
-            texel.model.material.color = color;
+            texel.model.set_color(color);
         }
     }
 }
 
 pub struct Texel {
-    pub model: Gm<Mesh, ColorMaterial>,
+    pub model: Model,
 }
 
 impl Texel {
@@ -1200,7 +1618,7 @@ impl Texel {
         pixel: [u8; 4],
         position: Vec3,
         direction: Direction,
-        context: &Context,
+        context: Option<&Context>,
     ) -> Self {
         use Direction::*;
         let positions = match direction {
@@ -1242,21 +1660,6 @@ impl Texel {
             ],
         };
 
-        let indices = vec![0, 1, 2, 2, 3, 0];
-
-        let uvs = vec![
-            Vec2::new(0.0, 1.0),
-            Vec2::new(1.0, 1.0),
-            Vec2::new(1.0, 0.0),
-            Vec2::new(0.0, 0.0),
-        ];
-
-        let mesh = CpuMesh {
-            positions: Positions::F32(positions),
-            indices: Indices::U8(indices),
-            uvs: Some(uvs),
-            ..Default::default()
-        };
         let pixel = {
             let r = correct_gamma(pixel[0]);
             let g = correct_gamma(pixel[1]);
@@ -1264,29 +1667,89 @@ impl Texel {
             let a = pixel[3];
             [r, g, b, a]
         };
-        let data = TextureData::RgbaU8(vec![pixel]);
-        let texture = CpuTexture {
-            name: format!("{name}"),
-            data,
-            width: 1,
-            height: 1,
-            min_filter: Interpolation::Nearest,
-            mag_filter: Interpolation::Nearest,
-            ..Default::default()
-        };
+        let model = Model::new(name, positions, vec![pixel], 1, 1, context);
+
+        Self { model }
+    }
+}
+
+pub struct Model {
+    pub positions: [Vec3; 4],
+    pub pixels: Vec<[u8; 4]>,
+    pub width: u32,
+    pub height: u32,
+    pub transformation: Mat4,
+    pub color: Srgba,
+    gpu: Option<Gm<Mesh, ColorMaterial>>,
+}
 
-        let material = ColorMaterial {
-            texture: Some(Texture2DRef::from_cpu_texture(&context, &texture)),
-            render_states: RenderStates {
-                cull: Cull::None,
+impl Model {
+    pub const UVS: [Vec2; 4] = [
+        Vec2::new(0.0, 1.0),
+        Vec2::new(1.0, 1.0),
+        Vec2::new(1.0, 0.0),
+        Vec2::new(0.0, 0.0),
+    ];
+    pub const INDICES: [[usize; 3]; 2] = [[0, 1, 2], [2, 3, 0]];
+
+    fn new(
+        name: &str,
+        positions: Vec<Vec3>,
+        pixels: Vec<[u8; 4]>,
+        width: u32,
+        height: u32,
+        context: Option<&Context>,
+    ) -> Self {
+        let gpu = context.map(|context| {
+            let mesh = CpuMesh {
+                positions: Positions::F32(positions.clone()),
+                indices: Indices::U8(Self::INDICES.concat().iter().map(|&i| i as u8).collect()),
+                uvs: Some(Self::UVS.to_vec()),
                 ..Default::default()
-            },
-            ..Default::default()
-        };
+            };
+            let texture = CpuTexture {
+                name: name.to_string(),
+                data: TextureData::RgbaU8(pixels.clone()),
+                width,
+                height,
+                min_filter: Interpolation::Nearest,
+                mag_filter: Interpolation::Nearest,
+                ..Default::default()
+            };
+            let material = ColorMaterial {
+                texture: Some(Texture2DRef::from_cpu_texture(context, &texture)),
+                render_states: RenderStates {
+                    cull: Cull::None,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            Gm::new(Mesh::new(context, &mesh), material)
+        });
+
+        Self {
+            positions: [positions[0], positions[1], positions[2], positions[3]],
+            pixels,
+            width,
+            height,
+            transformation: Mat4::identity(),
+            color: Srgba::WHITE,
+            gpu,
+        }
+    }
 
-        let model = Gm::new(Mesh::new(context, &mesh), material);
+    fn set_transformation(&mut self, transformation: Mat4) {
+        self.transformation = transformation;
+        if let Some(gpu) = &mut self.gpu {
+            gpu.set_transformation(transformation);
+        }
+    }
 
-        Self { model }
+    fn set_color(&mut self, color: Srgba) {
+        self.color = color;
+        if let Some(gpu) = &mut self.gpu {
+            gpu.material.color = color;
+        }
     }
 }
 
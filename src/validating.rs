@@ -0,0 +1,96 @@
+use anyhow::Context;
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage, imageops};
+use std::path::Path;
+
+const FACE: (u32, u32, u32, u32) = (8, 8, 8, 8);
+const HELMET_FACE: (u32, u32, u32, u32) = (40, 8, 8, 8);
+const LEGACY_LIMBS: [((u32, u32), (u32, u32)); 2] = [((0, 16), (16, 48)), ((40, 16), (32, 48))];
+const LEGACY_FACES: [(u32, u32, u32, u32, u32, u32); 6] = [
+    (4, 0, 4, 4, 4, 0),
+    (8, 0, 4, 4, 8, 0),
+    (8, 4, 4, 12, 0, 4),
+    (4, 4, 4, 12, 4, 4),
+    (0, 4, 4, 12, 8, 4),
+    (12, 4, 4, 12, 12, 4),
+];
+
+pub fn validate_skin(path: &Path) -> anyhow::Result<Vec<String>> {
+    let image = image::open(path).context("Not a readable png")?;
+    check_skin(&image)
+}
+
+pub fn load_skin(path: &Path) -> anyhow::Result<DynamicImage> {
+    let image = image::open(path).with_context(|| path.display().to_string())?;
+    check_skin(&image)?;
+    Ok(normalize_skin(&image))
+}
+
+pub fn decode_skin(bytes: &[u8]) -> anyhow::Result<DynamicImage> {
+    let image = image::load_from_memory(bytes)?;
+    check_skin(&image)?;
+    Ok(normalize_skin(&image))
+}
+
+pub fn check_skin(image: &DynamicImage) -> anyhow::Result<Vec<String>> {
+    let (width, height) = image.dimensions();
+    if width < 64 || width % 64 != 0 || (height != width && height * 2 != width) {
+        anyhow::bail!("Size {width}x{height} is not 64x64, 64x32 or an HD multiple");
+    }
+    let mut warnings = vec![];
+    if width > 64 {
+        warnings.push(format!(
+            "HD skin is downscaled to 64x{} with nearest-neighbour sampling, dropping detail",
+            height * 64 / width
+        ));
+    }
+    // Other bit depths and greyscale are converted to 8-bit RGBA by normalize_skin.
+    if !image.color().has_alpha() {
+        warnings.push("No alpha channel, overlays will be opaque".into());
+    }
+
+    let skin = normalize_skin(image);
+    if region_pixels(&skin, FACE).all(|pixel| pixel[3] == 0) {
+        warnings.push("Face is fully transparent".into());
+    }
+    let mut helmet = region_pixels(&skin, HELMET_FACE);
+    if let Some(first) = helmet.next()
+        && first[3] == 255
+        && helmet.all(|pixel| pixel == first)
+    {
+        warnings.push("Helmet overlay is a solid colour and will hide the face".into());
+    }
+    Ok(warnings)
+}
+
+pub fn normalize_skin(image: &DynamicImage) -> DynamicImage {
+    let mut skin = image.to_rgba8();
+    let scale = skin.width() / 64;
+    if scale > 1 {
+        skin = imageops::resize(
+            &skin,
+            64,
+            skin.height() / scale,
+            imageops::FilterType::Nearest,
+        );
+    }
+    if skin.height() == 32 {
+        let mut full = RgbaImage::from_pixel(64, 64, Rgba([0, 0, 0, 0]));
+        imageops::replace(&mut full, &skin, 0, 0);
+        for ((from_x, from_y), (to_x, to_y)) in LEGACY_LIMBS {
+            for (x, y, width, height, dx, dy) in LEGACY_FACES {
+                let face = imageops::crop_imm(&skin, from_x + x, from_y + y, width, height);
+                let face = imageops::flip_horizontal(&*face);
+                imageops::replace(&mut full, &face, (to_x + dx) as i64, (to_y + dy) as i64);
+            }
+        }
+        skin = full;
+    }
+    DynamicImage::ImageRgba8(skin)
+}
+
+fn region_pixels(
+    skin: &DynamicImage,
+    (x, y, width, height): (u32, u32, u32, u32),
+) -> impl Iterator<Item = Rgba<u8>> + '_ {
+    (y..y + height).flat_map(move |y| (x..x + width).map(move |x| skin.get_pixel(x, y)))
+}